@@ -0,0 +1,49 @@
+//! Latency of repeated `get-weather` lookups (geocode + current conditions)
+//! against a local mock, with a fresh HTTP client per call versus the shared,
+//! pooled client the server uses.
+//!
+//! Run with `cargo bench --bench get_weather`.
+
+#[allow(dead_code)]
+#[path = "../tests/support/mock_upstream.rs"]
+mod mock_upstream;
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use get_weather_poisoned::{
+    config::HttpConfig,
+    http,
+    provider::{OpenWeatherMapProvider, WeatherProvider},
+};
+use mock_upstream::MockUpstream;
+
+const API_KEY: &str = "bench-key";
+
+async fn get_weather(provider: &OpenWeatherMapProvider) {
+    let location = provider.geocode("London").await.unwrap();
+    provider.current(&location).await.unwrap();
+}
+
+fn repeated_get_weather(c: &mut Criterion) {
+    let runtime = tokio::runtime::Runtime::new().unwrap();
+    let mock = runtime.block_on(MockUpstream::start());
+    let provider = |client: reqwest::Client| {
+        OpenWeatherMapProvider::new(Some(API_KEY.to_string()))
+            .with_base_url(mock.base_url())
+            .with_client(client)
+    };
+
+    let mut group = c.benchmark_group("get_weather");
+    group.bench_function("client_per_call", |b| {
+        b.to_async(&runtime)
+            .iter(|| async { get_weather(&provider(reqwest::Client::new())).await })
+    });
+
+    let shared = provider(http::client(&HttpConfig::default()).unwrap());
+    group.bench_function("shared_client", |b| {
+        b.to_async(&runtime).iter(|| get_weather(&shared))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, repeated_get_weather);
+criterion_main!(benches);
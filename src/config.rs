@@ -0,0 +1,361 @@
+use std::{env, fmt, path::PathBuf, str::FromStr, time::Duration};
+use url::Url;
+
+/// Which upstream weather service backs the tools.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ProviderKind {
+    #[default]
+    OpenWeatherMap,
+    OpenMeteo,
+    MetNo,
+    /// Canned answers from a directory of JSON fixtures.
+    Fixture,
+    /// Deterministic generated weather, for development and CI.
+    Synthetic,
+}
+
+impl FromStr for ProviderKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "openweathermap" | "owm" => Ok(ProviderKind::OpenWeatherMap),
+            "open-meteo" | "openmeteo" => Ok(ProviderKind::OpenMeteo),
+            "met-no" | "metno" | "met.no" => Ok(ProviderKind::MetNo),
+            "fixture" | "fixtures" => Ok(ProviderKind::Fixture),
+            "synthetic" => Ok(ProviderKind::Synthetic),
+            other => Err(format!(
+                "Unknown weather provider '{}'. Expected one of: openweathermap, open-meteo, met-no, fixture, synthetic",
+                other
+            )),
+        }
+    }
+}
+
+impl ProviderKind {
+    /// Whether the provider fetches real weather over the network, as opposed
+    /// to serving canned or generated data.
+    pub fn is_live(self) -> bool {
+        !matches!(self, ProviderKind::Fixture | ProviderKind::Synthetic)
+    }
+}
+
+impl fmt::Display for ProviderKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            ProviderKind::OpenWeatherMap => "openweathermap",
+            ProviderKind::OpenMeteo => "open-meteo",
+            ProviderKind::MetNo => "met-no",
+            ProviderKind::Fixture => "fixture",
+            ProviderKind::Synthetic => "synthetic",
+        })
+    }
+}
+
+/// Output format for diagnostics.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    #[default]
+    Compact,
+    Pretty,
+    Json,
+}
+
+impl FromStr for LogFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "compact" => Ok(LogFormat::Compact),
+            "pretty" => Ok(LogFormat::Pretty),
+            "json" => Ok(LogFormat::Json),
+            other => Err(format!(
+                "Unknown log format '{}'. Expected one of: compact, pretty, json",
+                other
+            )),
+        }
+    }
+}
+
+/// Where and how diagnostics are written. Never stdout, which carries the
+/// MCP stdio transport.
+#[derive(Debug, Clone)]
+pub struct LogConfig {
+    /// `WEATHER_LOG` (falling back to `RUST_LOG`), an `EnvFilter` directive.
+    pub filter: String,
+    /// `WEATHER_LOG_FORMAT`, defaults to compact.
+    pub format: LogFormat,
+    /// `WEATHER_LOG_FILE`, appended to instead of stderr when set.
+    pub file: Option<PathBuf>,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        LogConfig {
+            filter: "info".to_string(),
+            format: LogFormat::default(),
+            file: None,
+        }
+    }
+}
+
+/// Tuning for the shared upstream HTTP client.
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    /// `WEATHER_HTTP_TIMEOUT_SECS`, total time allowed per request (default 10).
+    pub timeout: Duration,
+    /// `WEATHER_HTTP_CONNECT_TIMEOUT_SECS`, time allowed to establish a
+    /// connection (default 5).
+    pub connect_timeout: Duration,
+    /// `WEATHER_HTTP_POOL_MAX_IDLE`, idle connections kept per host (default 8).
+    pub pool_max_idle_per_host: usize,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        HttpConfig {
+            timeout: Duration::from_secs(10),
+            connect_timeout: Duration::from_secs(5),
+            pool_max_idle_per_host: 8,
+        }
+    }
+}
+
+/// Bounds for the in-memory response cache. A zero TTL disables caching for
+/// that kind of lookup.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    /// `WEATHER_CACHE_GEOCODE_TTL_SECS`, how long place lookups are reused
+    /// (default 7 days; coordinates rarely change).
+    pub geocode_ttl: Duration,
+    /// `WEATHER_CACHE_WEATHER_TTL_SECS`, how long observations and forecasts
+    /// are reused (default 10 minutes).
+    pub weather_ttl: Duration,
+    /// `WEATHER_CACHE_MAX_ENTRIES`, entries kept per cache before the least
+    /// recently used is evicted (default 1024).
+    pub max_entries: usize,
+    /// `WEATHER_CACHE_PATH`, a SQLite file that keeps cached lookups across
+    /// restarts. Unset keeps the cache in memory only.
+    pub path: Option<PathBuf>,
+    /// `WEATHER_CACHE_DISK_MAX_ENTRIES`, entries kept per cache in the file
+    /// before the oldest are evicted (default 10000).
+    pub disk_max_entries: usize,
+    /// `WEATHER_CACHE_MAX_STALE_SECS`, how long past expiry an entry is kept
+    /// to answer for an unreachable provider (default 1 day). `0` disables the
+    /// fallback.
+    pub max_stale: Duration,
+    /// `WEATHER_STALE_REFRESH_SECS`, how often a stale entry is refreshed in
+    /// the background while the provider is down (default 30).
+    pub refresh_interval: Duration,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            geocode_ttl: Duration::from_secs(7 * 24 * 60 * 60),
+            weather_ttl: Duration::from_secs(10 * 60),
+            max_entries: 1024,
+            path: None,
+            disk_max_entries: 10_000,
+            max_stale: Duration::from_secs(24 * 60 * 60),
+            refresh_interval: Duration::from_secs(30),
+        }
+    }
+}
+
+/// How failed upstream calls are retried, and when a provider is considered
+/// down.
+#[derive(Debug, Clone)]
+pub struct RetryConfig {
+    /// `WEATHER_RETRY_MAX_ATTEMPTS`, attempts per lookup including the first
+    /// (default 3). `1` disables retries.
+    pub max_attempts: u32,
+    /// `WEATHER_RETRY_BASE_DELAY_MS`, backoff before the first retry, doubled
+    /// for each one after (default 200). Each delay is jittered.
+    pub base_delay: Duration,
+    /// `WEATHER_RETRY_MAX_DELAY_MS`, cap on a single backoff, and on how long
+    /// a `Retry-After` is honored before giving up instead (default 5000).
+    pub max_delay: Duration,
+    /// `WEATHER_BREAKER_THRESHOLD`, consecutive failed calls that open the
+    /// circuit breaker (default 5). `0` disables the breaker.
+    pub breaker_threshold: u32,
+    /// `WEATHER_BREAKER_COOLDOWN_SECS`, how long an open breaker fails fast
+    /// before letting a trial call through (default 30).
+    pub breaker_cooldown: Duration,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        RetryConfig {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(200),
+            max_delay: Duration::from_secs(5),
+            breaker_threshold: 5,
+            breaker_cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+/// Client-side limits on calls to the provider, matching the API plan. Zero
+/// disables a limit.
+#[derive(Debug, Clone)]
+pub struct QuotaConfig {
+    /// `WEATHER_QUOTA_PER_MINUTE`, calls allowed per rolling minute (default
+    /// 60, OpenWeatherMap's free plan).
+    pub per_minute: u32,
+    /// `WEATHER_QUOTA_PER_DAY`, calls allowed per UTC day (default unlimited).
+    /// Counted across restarts when `WEATHER_CACHE_PATH` is set.
+    pub per_day: u32,
+}
+
+impl Default for QuotaConfig {
+    fn default() -> Self {
+        QuotaConfig {
+            per_minute: 60,
+            per_day: 0,
+        }
+    }
+}
+
+/// Server configuration, read from the environment at startup.
+#[derive(Debug, Clone, Default)]
+pub struct Config {
+    /// `WEATHER_PROVIDER`, defaults to OpenWeatherMap.
+    pub provider: ProviderKind,
+    /// `OPENWEATHER_API_KEY`, only required by the OpenWeatherMap provider.
+    pub openweather_api_key: Option<String>,
+    /// `OPENWEATHER_BASE_URL`, overrides `https://api.openweathermap.org`.
+    pub openweather_base_url: Option<Url>,
+    /// `WEATHER_FIXTURES_DIR`, required by the fixture provider.
+    pub fixtures_dir: Option<PathBuf>,
+    /// `WEATHER_SYNTHETIC_SEED`, seeds the synthetic provider (default 0).
+    pub synthetic_seed: u64,
+    pub log: LogConfig,
+    pub http: HttpConfig,
+    pub cache: CacheConfig,
+    pub retry: RetryConfig,
+    pub quota: QuotaConfig,
+    /// `WEATHER_REDACT_SECRETS`, extra comma-separated values to scrub from
+    /// all output. `OPENWEATHER_API_KEY` is always scrubbed.
+    pub extra_secrets: Vec<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self, String> {
+        let provider = match env::var("WEATHER_PROVIDER") {
+            Ok(value) if !value.trim().is_empty() => value.parse()?,
+            _ => ProviderKind::default(),
+        };
+
+        let synthetic_seed = match env::var("WEATHER_SYNTHETIC_SEED") {
+            Ok(value) => value
+                .trim()
+                .parse()
+                .map_err(|_| format!("Invalid WEATHER_SYNTHETIC_SEED '{}'", value))?,
+            Err(_) => 0,
+        };
+
+        let log = LogConfig {
+            filter: env::var("WEATHER_LOG")
+                .or_else(|_| env::var("RUST_LOG"))
+                .unwrap_or_else(|_| LogConfig::default().filter),
+            format: match env::var("WEATHER_LOG_FORMAT") {
+                Ok(value) if !value.trim().is_empty() => value.parse()?,
+                _ => LogFormat::default(),
+            },
+            file: env::var_os("WEATHER_LOG_FILE").map(PathBuf::from),
+        };
+
+        let defaults = HttpConfig::default();
+        let http = HttpConfig {
+            timeout: parse_env::<u64>("WEATHER_HTTP_TIMEOUT_SECS")?
+                .map_or(defaults.timeout, Duration::from_secs),
+            connect_timeout: parse_env::<u64>("WEATHER_HTTP_CONNECT_TIMEOUT_SECS")?
+                .map_or(defaults.connect_timeout, Duration::from_secs),
+            pool_max_idle_per_host: parse_env("WEATHER_HTTP_POOL_MAX_IDLE")?
+                .unwrap_or(defaults.pool_max_idle_per_host),
+        };
+
+        let defaults = CacheConfig::default();
+        let cache = CacheConfig {
+            geocode_ttl: parse_env::<u64>("WEATHER_CACHE_GEOCODE_TTL_SECS")?
+                .map_or(defaults.geocode_ttl, Duration::from_secs),
+            weather_ttl: parse_env::<u64>("WEATHER_CACHE_WEATHER_TTL_SECS")?
+                .map_or(defaults.weather_ttl, Duration::from_secs),
+            max_entries: parse_env("WEATHER_CACHE_MAX_ENTRIES")?.unwrap_or(defaults.max_entries),
+            path: env::var_os("WEATHER_CACHE_PATH")
+                .filter(|path| !path.is_empty())
+                .map(PathBuf::from),
+            disk_max_entries: parse_env("WEATHER_CACHE_DISK_MAX_ENTRIES")?
+                .unwrap_or(defaults.disk_max_entries),
+            max_stale: parse_env::<u64>("WEATHER_CACHE_MAX_STALE_SECS")?
+                .map_or(defaults.max_stale, Duration::from_secs),
+            refresh_interval: parse_env::<u64>("WEATHER_STALE_REFRESH_SECS")?
+                .map_or(defaults.refresh_interval, Duration::from_secs),
+        };
+
+        let defaults = RetryConfig::default();
+        let retry = RetryConfig {
+            max_attempts: parse_env("WEATHER_RETRY_MAX_ATTEMPTS")?
+                .unwrap_or(defaults.max_attempts)
+                .max(1),
+            base_delay: parse_env::<u64>("WEATHER_RETRY_BASE_DELAY_MS")?
+                .map_or(defaults.base_delay, Duration::from_millis),
+            max_delay: parse_env::<u64>("WEATHER_RETRY_MAX_DELAY_MS")?
+                .map_or(defaults.max_delay, Duration::from_millis),
+            breaker_threshold: parse_env("WEATHER_BREAKER_THRESHOLD")?
+                .unwrap_or(defaults.breaker_threshold),
+            breaker_cooldown: parse_env::<u64>("WEATHER_BREAKER_COOLDOWN_SECS")?
+                .map_or(defaults.breaker_cooldown, Duration::from_secs),
+        };
+
+        let defaults = QuotaConfig::default();
+        let quota = QuotaConfig {
+            per_minute: parse_env("WEATHER_QUOTA_PER_MINUTE")?.unwrap_or(defaults.per_minute),
+            per_day: parse_env("WEATHER_QUOTA_PER_DAY")?.unwrap_or(defaults.per_day),
+        };
+
+        let openweather_base_url = match env::var("OPENWEATHER_BASE_URL") {
+            Ok(value) if !value.trim().is_empty() => Some(
+                Url::parse(value.trim())
+                    .map_err(|e| format!("Invalid OPENWEATHER_BASE_URL '{}': {}", value, e))?,
+            ),
+            _ => None,
+        };
+
+        Ok(Config {
+            provider,
+            openweather_api_key: env::var("OPENWEATHER_API_KEY").ok(),
+            openweather_base_url,
+            fixtures_dir: env::var_os("WEATHER_FIXTURES_DIR").map(PathBuf::from),
+            synthetic_seed,
+            log,
+            http,
+            cache,
+            retry,
+            quota,
+            extra_secrets: env::var("WEATHER_REDACT_SECRETS")
+                .map(|value| {
+                    value
+                        .split(',')
+                        .map(str::trim)
+                        .filter(|s| !s.is_empty())
+                        .map(str::to_string)
+                        .collect()
+                })
+                .unwrap_or_default(),
+        })
+    }
+}
+
+/// Reads and parses an optional variable, treating blank values as unset.
+fn parse_env<T: FromStr>(name: &str) -> Result<Option<T>, String> {
+    match env::var(name) {
+        Ok(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("Invalid {} '{}'", name, value)),
+        _ => Ok(None),
+    }
+}
@@ -0,0 +1,163 @@
+use rmcp::model::{CallToolResult, Content, ErrorData};
+use serde_json::{json, Value};
+
+/// Everything that can go wrong while answering a tool call.
+///
+/// Argument problems are the caller's fault and surface as JSON-RPC errors;
+/// everything else becomes a tool result with `is_error: true` and a
+/// machine-readable `code`, so clients can decide whether to retry.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum WeatherError {
+    /// A required setting, named by its environment variable, is absent.
+    #[error("{0} not set")]
+    MissingConfig(&'static str),
+    #[error("City not found: {0}")]
+    CityNotFound(String),
+    #[error("Failed to get {what} data: upstream rejected the credentials (HTTP {status})")]
+    UpstreamAuth { what: &'static str, status: u16 },
+    #[error("Failed to get {what} data: rate limited by upstream")]
+    RateLimited {
+        what: &'static str,
+        /// Seconds to wait, from the upstream `Retry-After` header.
+        retry_after: Option<u64>,
+    },
+    #[error("Failed to get {what} data: upstream timed out")]
+    UpstreamTimeout { what: &'static str },
+    /// Connection failures and unexpected HTTP statuses.
+    #[error("Failed to get {what} data: {message}")]
+    Upstream {
+        what: &'static str,
+        status: Option<u16>,
+        message: String,
+    },
+    #[error("Failed to parse {what} data: {message}")]
+    Parse { what: &'static str, message: String },
+    /// Recent calls to the provider kept failing, so it is not being called
+    /// until the breaker's cooldown has passed.
+    #[error("The {provider} weather service is unavailable; retry in {retry_after}s")]
+    CircuitOpen {
+        provider: &'static str,
+        retry_after: u64,
+    },
+    /// The client-side limiter refused the call before it reached the
+    /// provider, so the API key's plan limits are never exceeded.
+    #[error(
+        "Reached the per-{window} quota of {limit} calls to {provider}; retry in {retry_after}s"
+    )]
+    QuotaExceeded {
+        provider: &'static str,
+        /// `"minute"` or `"day"`.
+        window: &'static str,
+        limit: u32,
+        retry_after: u64,
+    },
+    /// The configured provider has no data of this kind.
+    #[error("The {provider} provider does not support {feature}")]
+    Unsupported {
+        provider: &'static str,
+        feature: &'static str,
+    },
+    /// The provider keeps no data for a requested date.
+    #[error("The {provider} provider has no weather history for {date}; it covers {coverage}")]
+    DateUnavailable {
+        provider: &'static str,
+        date: String,
+        /// The dates it does cover, in words.
+        coverage: &'static str,
+    },
+    #[error("{0}")]
+    InvalidArguments(String),
+}
+
+impl WeatherError {
+    /// Stable identifier for the error kind, exposed to clients.
+    pub fn code(&self) -> &'static str {
+        match self {
+            WeatherError::MissingConfig(_) => "missing_config",
+            WeatherError::CityNotFound(_) => "city_not_found",
+            WeatherError::UpstreamAuth { .. } => "upstream_auth",
+            WeatherError::RateLimited { .. } => "rate_limited",
+            WeatherError::UpstreamTimeout { .. } => "upstream_timeout",
+            WeatherError::Upstream { .. } => "upstream_unavailable",
+            WeatherError::Parse { .. } => "parse_error",
+            WeatherError::CircuitOpen { .. } => "circuit_open",
+            WeatherError::QuotaExceeded { .. } => "quota_exceeded",
+            WeatherError::Unsupported { .. } => "unsupported",
+            WeatherError::DateUnavailable { .. } => "date_unavailable",
+            WeatherError::InvalidArguments(_) => "invalid_arguments",
+        }
+    }
+
+    /// Whether the same call may succeed if retried later.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            WeatherError::RateLimited { .. }
+            | WeatherError::UpstreamTimeout { .. }
+            | WeatherError::CircuitOpen { .. }
+            | WeatherError::QuotaExceeded { .. } => true,
+            WeatherError::Upstream { status, .. } => status.is_none_or(|s| s >= 500),
+            _ => false,
+        }
+    }
+
+    /// Whether the call got as far as the provider. Quota refusals, open
+    /// breakers, missing settings, unsupported features and dates outside
+    /// the provider's coverage are all decided before any request is sent.
+    pub fn reached_upstream(&self) -> bool {
+        matches!(
+            self,
+            WeatherError::UpstreamTimeout { .. }
+                | WeatherError::Upstream { .. }
+                | WeatherError::RateLimited { .. }
+                | WeatherError::UpstreamAuth { .. }
+                | WeatherError::Parse { .. }
+                | WeatherError::CityNotFound(_)
+        )
+    }
+
+    fn details(&self) -> Value {
+        let mut details = json!({
+            "code": self.code(),
+            "message": self.to_string(),
+            "retryable": self.is_retryable(),
+        });
+        match self {
+            WeatherError::RateLimited {
+                retry_after: Some(secs),
+                ..
+            }
+            | WeatherError::CircuitOpen {
+                retry_after: secs, ..
+            }
+            | WeatherError::QuotaExceeded {
+                retry_after: secs, ..
+            } => details["retry_after_secs"] = json!(secs),
+            _ => {}
+        }
+        details
+    }
+
+    /// Converts the error into what `call_tool` returns for it.
+    pub fn into_call_result(self) -> Result<CallToolResult, ErrorData> {
+        match self {
+            WeatherError::InvalidArguments(_) => Err(self.into()),
+            _ => Ok(CallToolResult {
+                content: vec![
+                    Content::text(self.to_string()),
+                    Content::json(json!({ "error": self.details() }))?,
+                ],
+                is_error: Some(true),
+            }),
+        }
+    }
+}
+
+impl From<WeatherError> for ErrorData {
+    fn from(error: WeatherError) -> Self {
+        let data = Some(error.details());
+        match error {
+            WeatherError::InvalidArguments(message) => ErrorData::invalid_params(message, data),
+            other => ErrorData::internal_error(other.to_string(), data),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! Just enough GeoJSON to tell whether a point lies inside an area.
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A GeoJSON position: longitude, then latitude, in decimal degrees. Any
+/// altitude given after them is dropped.
+pub type Position = [f64; 2];
+
+/// A closed ring of positions whose first and last positions coincide.
+pub type Ring = Vec<Position>;
+
+/// An area as described by a GeoJSON geometry object.
+///
+/// Only polygons enclose anything, so every other geometry type is read as
+/// [`Geometry::Other`] and contains no points.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "coordinates", try_from = "RawGeometry")]
+pub enum Geometry {
+    /// An outer ring followed by any holes cut out of it.
+    Polygon(Vec<Ring>),
+    MultiPolygon(Vec<Vec<Ring>>),
+    Other,
+}
+
+/// A geometry of any type, before its coordinates are read.
+#[derive(Deserialize)]
+struct RawGeometry {
+    #[serde(rename = "type")]
+    kind: String,
+    #[serde(default)]
+    coordinates: Value,
+}
+
+impl TryFrom<RawGeometry> for Geometry {
+    type Error = serde_json::Error;
+
+    fn try_from(raw: RawGeometry) -> Result<Self, Self::Error> {
+        match raw.kind.as_str() {
+            "Polygon" => {
+                let rings: Vec<Vec<Vec<f64>>> = serde_json::from_value(raw.coordinates)?;
+                polygon(rings).map(Geometry::Polygon)
+            }
+            "MultiPolygon" => {
+                let polygons: Vec<Vec<Vec<Vec<f64>>>> = serde_json::from_value(raw.coordinates)?;
+                polygons
+                    .into_iter()
+                    .map(polygon)
+                    .collect::<Result<_, _>>()
+                    .map(Geometry::MultiPolygon)
+            }
+            _ => Ok(Geometry::Other),
+        }
+    }
+}
+
+fn polygon(rings: Vec<Vec<Vec<f64>>>) -> Result<Vec<Ring>, serde_json::Error> {
+    rings
+        .into_iter()
+        .map(|ring| ring.into_iter().map(position).collect())
+        .collect()
+}
+
+fn position(coordinates: Vec<f64>) -> Result<Position, serde_json::Error> {
+    match coordinates[..] {
+        [lon, lat, ..] => Ok([lon, lat]),
+        _ => Err(serde::de::Error::custom(
+            "a position needs a longitude and a latitude",
+        )),
+    }
+}
+
+impl Geometry {
+    /// Whether the point at `lat`, `lon` lies inside the area. Points exactly
+    /// on an edge may fall either way.
+    pub fn contains(&self, lat: f64, lon: f64) -> bool {
+        match self {
+            Geometry::Polygon(rings) => polygon_contains(rings, [lon, lat]),
+            Geometry::MultiPolygon(polygons) => polygons
+                .iter()
+                .any(|rings| polygon_contains(rings, [lon, lat])),
+            Geometry::Other => false,
+        }
+    }
+}
+
+fn polygon_contains(rings: &[Ring], point: Position) -> bool {
+    let Some((outer, holes)) = rings.split_first() else {
+        return false;
+    };
+    ring_contains(outer, point) && !holes.iter().any(|hole| ring_contains(hole, point))
+}
+
+/// Even-odd ray casting: a ray from the point crosses the ring's edges an odd
+/// number of times if and only if the point is inside.
+fn ring_contains(ring: &[Position], [x, y]: Position) -> bool {
+    let mut inside = false;
+    let Some(mut previous) = ring.last() else {
+        return false;
+    };
+    for current in ring {
+        let ([x1, y1], [x2, y2]) = (*previous, *current);
+        if (y1 > y) != (y2 > y) && x < x1 + (y - y1) * (x2 - x1) / (y2 - y1) {
+            inside = !inside;
+        }
+        previous = current;
+    }
+    inside
+}
@@ -0,0 +1,1245 @@
+use chrono::NaiveDate;
+use rmcp::{
+    handler::server::ServerHandler,
+    model::{
+        CallToolRequestParam, CallToolResult, Content, ErrorData, ListToolsResult,
+        PaginatedRequestParam, RawContent, ServerCapabilities, ServerInfo, Tool, ToolsCapability,
+    },
+    service::{RequestContext, RoleServer},
+};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{borrow::Cow, sync::Arc, time::Instant};
+
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Alert, DailyWeather, ForecastEntry, Location, LocationInput, Nowcast,
+    Observation, PlaceQuery, UvPollenDay,
+};
+use crate::provider::WeatherProvider;
+use crate::redact;
+
+/// The ways a tool call can name a location. Exactly one of `city`, `lat`
+/// and `lon`, `postal_code` or `location_id` must be given; see
+/// [`LocationArgs::parse`].
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct LocationArgs {
+    city: Option<String>,
+    /// Qualifies `city`.
+    state: Option<String>,
+    /// Qualifies `city`, or is required with `postal_code`.
+    country: Option<String>,
+    lat: Option<f64>,
+    lon: Option<f64>,
+    postal_code: Option<String>,
+    /// A candidate ID from `resolve-location`.
+    location_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetWeatherRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+    /// List the locations matching `city` instead of fetching the weather.
+    #[serde(default)]
+    candidates: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetAirQualityRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+    hours: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetAlertsRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetUvPollenRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+    days: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetNowcastRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetHistoricalWeatherRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+    /// A single day, or `start_date` and `end_date` for a range.
+    date: Option<String>,
+    start_date: Option<String>,
+    end_date: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ResolveLocationRequest {
+    city: String,
+    state: Option<String>,
+    country: Option<String>,
+    limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ReverseGeocodeRequest {
+    lat: f64,
+    lon: f64,
+}
+
+/// A geocoding match as listed to the client, with the ID that selects it.
+#[derive(Serialize)]
+struct Candidate<'a> {
+    id: String,
+    #[serde(flatten)]
+    location: &'a Location,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GetForecastRequest {
+    #[serde(flatten)]
+    location: LocationArgs,
+    hours: Option<u32>,
+}
+
+// Bounds for the forecast horizon; providers may round to their own step size.
+const FORECAST_MIN_HOURS: u32 = 1;
+const FORECAST_MAX_HOURS: u32 = 120;
+const FORECAST_DEFAULT_HOURS: u32 = 24;
+
+// Air quality forecasts cover four days at most; by default only current
+// readings are returned.
+const AIR_QUALITY_MAX_HOURS: u32 = 96;
+
+// Pollen is forecast four days ahead; by default only today is returned.
+const UV_POLLEN_MAX_DAYS: u32 = 4;
+const UV_POLLEN_DEFAULT_DAYS: u32 = 1;
+
+/// How far ahead get-nowcast looks, in minutes.
+const NOWCAST_MINUTES: u32 = 60;
+
+/// Most days get-historical-weather returns for one range.
+const HISTORY_MAX_DAYS: i64 = 31;
+
+/// Longest place name accepted from a tool call, in characters.
+const MAX_PLACE_QUERY_CHARS: usize = 100;
+
+// How many geocoding matches resolve-location lists, unless the provider
+// lists fewer.
+const CANDIDATES_MAX: u32 = 10;
+const CANDIDATES_DEFAULT: u32 = 5;
+
+#[derive(Clone)]
+pub struct WeatherServerHandler {
+    provider: Arc<dyn WeatherProvider>,
+}
+
+impl WeatherServerHandler {
+    pub fn new(provider: Arc<dyn WeatherProvider>) -> Self {
+        WeatherServerHandler { provider }
+    }
+
+    /// Most candidates resolve-location offers, capped by what the provider
+    /// can list for one query.
+    fn candidates_max(&self) -> u32 {
+        CANDIDATES_MAX.min(self.provider.max_candidates()).max(1)
+    }
+
+    /// Lists up to `limit` locations matching `place`, best match first.
+    ///
+    /// Not every provider can filter by state or country upstream, so the
+    /// country is checked here too. Providers name states inconsistently
+    /// ("IL" or "Illinois"), so matches in the requested state are ranked
+    /// first rather than the others dropped.
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn find_candidates(
+        &self,
+        place: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        let mut candidates = self.provider.geocode_candidates(place, limit).await?;
+        candidates.retain(|location| place.matches_country(location));
+        candidates.sort_by_key(|location| !place.matches_state(location));
+        candidates.truncate(limit as usize);
+        if candidates.is_empty() {
+            return Err(WeatherError::CityNotFound(place.to_string()));
+        }
+        Ok(candidates)
+    }
+
+    /// Turns a tool call's location into coordinates, geocoding only when it
+    /// was not given as coordinates.
+    async fn locate(&self, input: &LocationInput) -> Result<Location, WeatherError> {
+        match input {
+            LocationInput::Place(place) if place.state.is_none() && place.country.is_none() => {
+                self.provider.geocode(&place.name).await
+            }
+            LocationInput::Place(place) => {
+                let candidates = self.find_candidates(place, CANDIDATES_MAX).await?;
+                Ok(candidates
+                    .into_iter()
+                    .next()
+                    .expect("at least one candidate"))
+            }
+            LocationInput::Coordinates { lat, lon } => Ok(Location::at(*lat, *lon)),
+            LocationInput::Known(location) => Ok(location.clone()),
+            LocationInput::PostalCode { code, country } => {
+                self.provider.geocode_postal_code(code, country).await
+            }
+        }
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_weather(&self, input: &LocationInput) -> Result<Observation, WeatherError> {
+        let location = self.locate(input).await?;
+
+        self.provider.current(&location).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_forecast(
+        &self,
+        input: &LocationInput,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.forecast(&location, hours).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_air_quality(
+        &self,
+        input: &LocationInput,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.air_quality(&location, hours).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_uv_pollen(
+        &self,
+        input: &LocationInput,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.uv_pollen(&location, days).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_nowcast(&self, input: &LocationInput) -> Result<Nowcast, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.nowcast(&location).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_history(
+        &self,
+        input: &LocationInput,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.history(&location, start, end).await
+    }
+
+    #[tracing::instrument(skip(self), fields(provider = self.provider.name()))]
+    async fn fetch_alerts(&self, input: &LocationInput) -> Result<Vec<Alert>, WeatherError> {
+        let location = self.locate(input).await?;
+        self.provider.alerts(&location).await
+    }
+
+    async fn dispatch_tool(
+        &self,
+        request: CallToolRequestParam,
+    ) -> Result<CallToolResult, ErrorData> {
+        match request.name.as_ref() {
+            "get-weather" => {
+                let (params, location) = match parse_arguments(
+                    "get-weather",
+                    request.arguments,
+                    "a location and an optional 'candidates' field",
+                )
+                .and_then(|params: GetWeatherRequest| {
+                    let location = params.location.parse("get-weather")?;
+                    Ok((params, location))
+                }) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                if params.candidates {
+                    let LocationInput::Place(place) = &location else {
+                        return WeatherError::InvalidArguments(
+                            "Invalid arguments for get-weather: 'candidates' lists the places \
+                             matching 'city'."
+                                .to_string(),
+                        )
+                        .into_call_result();
+                    };
+                    return match self.find_candidates(place, CANDIDATES_DEFAULT).await {
+                        Ok(candidates) => candidates_result(place, &candidates),
+                        Err(e) => e.into_call_result(),
+                    };
+                }
+
+                match self.fetch_weather(&location).await {
+                    Ok(observation) => Ok(CallToolResult {
+                        content: vec![
+                            Content::text(observation.summary()),
+                            Content::json(&observation)?,
+                        ],
+                        is_error: Some(false),
+                    }),
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-forecast" => {
+                let (params, location) = match parse_arguments(
+                    "get-forecast",
+                    request.arguments,
+                    "a location and an optional 'hours' field",
+                )
+                .and_then(|params: GetForecastRequest| {
+                    let location = params.location.parse("get-forecast")?;
+                    Ok((params, location))
+                }) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                let hours = params
+                    .hours
+                    .unwrap_or(FORECAST_DEFAULT_HOURS)
+                    .clamp(FORECAST_MIN_HOURS, FORECAST_MAX_HOURS);
+
+                match self.fetch_forecast(&location, hours).await {
+                    Ok(entries) => {
+                        let lines: Vec<String> = entries
+                            .iter()
+                            .map(|e| {
+                                format!(
+                                    "{}: {}°C, {}, {:.0}% chance of precipitation, wind {} m/s",
+                                    e.time,
+                                    e.temp,
+                                    e.conditions,
+                                    e.precipitation_probability * 100.0,
+                                    e.wind_speed
+                                )
+                            })
+                            .collect();
+                        Ok(CallToolResult {
+                            content: vec![
+                                Content::text(format!(
+                                    "Forecast for {} (next {} hours):\n{}",
+                                    location,
+                                    hours,
+                                    lines.join("\n")
+                                )),
+                                Content::json(&entries)?,
+                            ],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-air-quality" => {
+                let (params, location) = match parse_arguments(
+                    "get-air-quality",
+                    request.arguments,
+                    "a location and an optional 'hours' field",
+                )
+                .and_then(|params: GetAirQualityRequest| {
+                    let location = params.location.parse("get-air-quality")?;
+                    Ok((params, location))
+                }) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                let hours = params.hours.unwrap_or(0).min(AIR_QUALITY_MAX_HOURS);
+                match self.fetch_air_quality(&location, hours).await {
+                    Ok(report) => {
+                        let mut text =
+                            format!("Air quality in {}: {}.", location, report.current.summary());
+                        if !report.forecast.is_empty() {
+                            let lines: Vec<String> = report
+                                .forecast
+                                .iter()
+                                .map(|entry| format!("{}: {}", entry.time, entry.summary()))
+                                .collect();
+                            text.push_str(&format!(
+                                "\nForecast (next {} hours):\n{}",
+                                hours,
+                                lines.join("\n")
+                            ));
+                        }
+                        Ok(CallToolResult {
+                            content: vec![Content::text(text), Content::json(&report)?],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-alerts" => {
+                let location = match parse_arguments("get-alerts", request.arguments, "a location")
+                    .and_then(|params: GetAlertsRequest| params.location.parse("get-alerts"))
+                {
+                    Ok(location) => location,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                match self.fetch_alerts(&location).await {
+                    Ok(alerts) => {
+                        let text = match alerts.len() {
+                            0 => format!("No active alerts for {}.", location),
+                            count => {
+                                let lines: Vec<String> =
+                                    alerts.iter().map(Alert::summary).collect();
+                                format!(
+                                    "{} active alert{} for {}:\n{}",
+                                    count,
+                                    if count == 1 { "" } else { "s" },
+                                    location,
+                                    lines.join("\n")
+                                )
+                            }
+                        };
+                        Ok(CallToolResult {
+                            content: vec![
+                                Content::text(text),
+                                Content::json(serde_json::json!({ "alerts": alerts }))?,
+                            ],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-uv-pollen" => {
+                let (params, location) = match parse_arguments(
+                    "get-uv-pollen",
+                    request.arguments,
+                    "a location and an optional 'days' field",
+                )
+                .and_then(|params: GetUvPollenRequest| {
+                    let location = params.location.parse("get-uv-pollen")?;
+                    Ok((params, location))
+                }) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                let days = params
+                    .days
+                    .unwrap_or(UV_POLLEN_DEFAULT_DAYS)
+                    .clamp(1, UV_POLLEN_MAX_DAYS);
+                match self.fetch_uv_pollen(&location, days).await {
+                    Ok(forecast) => {
+                        let lines: Vec<String> =
+                            forecast.iter().map(UvPollenDay::summary).collect();
+                        let mut text = format!(
+                            "UV index and pollen for {}:\n{}",
+                            location,
+                            lines.join("\n")
+                        );
+                        if !forecast.iter().any(UvPollenDay::has_pollen) {
+                            text.push_str("\nPollen forecasts are only available in Europe.");
+                        }
+                        Ok(CallToolResult {
+                            content: vec![Content::text(text), Content::json(&forecast)?],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-nowcast" => {
+                let location = match parse_arguments("get-nowcast", request.arguments, "a location")
+                    .and_then(|params: GetNowcastRequest| params.location.parse("get-nowcast"))
+                {
+                    Ok(location) => location,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                match self.fetch_nowcast(&location).await {
+                    Ok(nowcast) => {
+                        // The nowcast may have been cached a minute or two ago.
+                        let now = chrono::Utc::now().timestamp();
+                        let nowcast = nowcast.upcoming(now, NOWCAST_MINUTES);
+                        let summary = nowcast.summary(now);
+                        let mut text = format!(
+                            "Precipitation in {} over the next hour: {}.",
+                            location, summary
+                        );
+                        if nowcast.resolution_minutes > 1 {
+                            text.push_str(&format!(
+                                " The provider only forecasts in {}-minute steps.",
+                                nowcast.resolution_minutes
+                            ));
+                        }
+                        Ok(CallToolResult {
+                            content: vec![
+                                Content::text(text),
+                                Content::json(serde_json::json!({
+                                    "summary": summary,
+                                    "resolution_minutes": nowcast.resolution_minutes,
+                                    "steps": nowcast.steps,
+                                }))?,
+                            ],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "get-historical-weather" => {
+                let (start, end, location) = match parse_arguments(
+                    "get-historical-weather",
+                    request.arguments,
+                    "a location and 'date', or 'start_date' and 'end_date'",
+                )
+                .and_then(|params: GetHistoricalWeatherRequest| {
+                    let location = params.location.parse("get-historical-weather")?;
+                    let (start, end) = params.dates()?;
+                    Ok((start, end, location))
+                }) {
+                    Ok(parsed) => parsed,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                match self.fetch_history(&location, start, end).await {
+                    Ok(days) => {
+                        let period = if start == end {
+                            format!("on {}", start)
+                        } else {
+                            format!("from {} to {}", start, end)
+                        };
+                        let lines: Vec<String> = days.iter().map(DailyWeather::summary).collect();
+                        Ok(CallToolResult {
+                            content: vec![
+                                Content::text(format!(
+                                    "Weather in {} {}:\n{}",
+                                    location,
+                                    period,
+                                    lines.join("\n")
+                                )),
+                                Content::json(&days)?,
+                            ],
+                            is_error: Some(false),
+                        })
+                    }
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "resolve-location" => {
+                let params: ResolveLocationRequest = match parse_arguments(
+                    "resolve-location",
+                    request.arguments,
+                    "'city' and optional 'state', 'country' and 'limit' fields",
+                )
+                .and_then(|params: ResolveLocationRequest| {
+                    validate_place(
+                        "resolve-location",
+                        &params.city,
+                        &params.state,
+                        &params.country,
+                    )?;
+                    Ok(params)
+                }) {
+                    Ok(params) => params,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                let limit = params
+                    .limit
+                    .unwrap_or(CANDIDATES_DEFAULT)
+                    .clamp(1, self.candidates_max());
+                let place = PlaceQuery {
+                    name: params.city,
+                    state: params.state,
+                    country: params.country,
+                };
+                match self.find_candidates(&place, limit).await {
+                    Ok(candidates) => candidates_result(&place, &candidates),
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            "reverse-geocode" => {
+                let params: ReverseGeocodeRequest = match parse_arguments(
+                    "reverse-geocode",
+                    request.arguments,
+                    "'lat' and 'lon' fields",
+                )
+                .and_then(|params: ReverseGeocodeRequest| {
+                    validate_coordinates("reverse-geocode", params.lat, params.lon)?;
+                    Ok(params)
+                }) {
+                    Ok(params) => params,
+                    Err(e) => return e.into_call_result(),
+                };
+
+                match self.provider.reverse_geocode(params.lat, params.lon).await {
+                    Ok(location) => Ok(CallToolResult {
+                        content: vec![
+                            Content::text(format!(
+                                "{:.4}, {:.4} is in {}.",
+                                params.lat,
+                                params.lon,
+                                location.label()
+                            )),
+                            Content::json(Candidate {
+                                id: location.id(),
+                                location: &location,
+                            })?,
+                        ],
+                        is_error: Some(false),
+                    }),
+                    Err(e) => e.into_call_result(),
+                }
+            }
+            _ => Err(ErrorData::invalid_params(
+                format!("Unknown tool: {}", request.name),
+                None,
+            )),
+        }
+    }
+}
+
+impl GetHistoricalWeatherRequest {
+    /// The first and last day asked for, checked to form a range of past days
+    /// no longer than [`HISTORY_MAX_DAYS`].
+    fn dates(&self) -> Result<(NaiveDate, NaiveDate), WeatherError> {
+        let invalid = |problem: &str| {
+            WeatherError::InvalidArguments(format!(
+                "Invalid arguments for get-historical-weather: {}.",
+                problem
+            ))
+        };
+        let parse = |field: &str, value: &str| {
+            NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d")
+                .map_err(|_| invalid(&format!("'{}' must be a date like 2024-01-31", field)))
+        };
+
+        let (start, end) = match (&self.date, &self.start_date, &self.end_date) {
+            (Some(date), None, None) => {
+                let date = parse("date", date)?;
+                (date, date)
+            }
+            (None, Some(start), Some(end)) => {
+                (parse("start_date", start)?, parse("end_date", end)?)
+            }
+            (None, Some(_), None) | (None, None, Some(_)) => {
+                return Err(invalid(
+                    "'start_date' and 'end_date' must be given together",
+                ))
+            }
+            _ => return Err(invalid("give either 'date' or 'start_date' and 'end_date'")),
+        };
+
+        if end < start {
+            return Err(invalid("'end_date' must not be before 'start_date'"));
+        }
+        if end >= chrono::Utc::now().date_naive() {
+            return Err(invalid(
+                "dates must be before today (UTC); use get-forecast for today and later",
+            ));
+        }
+        if (end - start).num_days() >= HISTORY_MAX_DAYS {
+            return Err(invalid(&format!(
+                "a range can span at most {} days",
+                HISTORY_MAX_DAYS
+            )));
+        }
+        Ok((start, end))
+    }
+}
+
+impl LocationArgs {
+    /// Checks that exactly one form of location was given and that it is well
+    /// formed, before it reaches any upstream request.
+    fn parse(&self, tool: &str) -> Result<LocationInput, WeatherError> {
+        let invalid = |problem: &str| {
+            WeatherError::InvalidArguments(format!("Invalid arguments for {}: {}.", tool, problem))
+        };
+        let forms = [
+            self.city.is_some(),
+            self.lat.is_some() || self.lon.is_some(),
+            self.postal_code.is_some(),
+            self.location_id.is_some(),
+        ];
+        if forms.into_iter().filter(|&given| given).count() != 1 {
+            return Err(invalid(
+                "give exactly one of 'city', 'lat' and 'lon', 'postal_code' with 'country', \
+                 or 'location_id'",
+            ));
+        }
+        if self.state.is_some() && self.city.is_none() {
+            return Err(invalid("'state' can only qualify 'city'"));
+        }
+
+        if let Some(city) = &self.city {
+            validate_place(tool, city, &self.state, &self.country)?;
+            return Ok(LocationInput::Place(PlaceQuery {
+                name: city.clone(),
+                state: self.state.clone(),
+                country: self.country.clone(),
+            }));
+        }
+        if let Some(code) = &self.postal_code {
+            validate_text(tool, "postal_code", code)?;
+            let country = self
+                .country
+                .as_deref()
+                .ok_or_else(|| invalid("'postal_code' needs a 'country'"))?;
+            validate_country(tool, country)?;
+            return Ok(LocationInput::PostalCode {
+                code: code.trim().to_string(),
+                country: country.trim().to_ascii_uppercase(),
+            });
+        }
+        if self.country.is_some() {
+            return Err(invalid(
+                "'country' can only qualify 'city' or 'postal_code'",
+            ));
+        }
+        if let Some(id) = &self.location_id {
+            // The label is as untrusted as a 'city' and is held to the same rules.
+            let location = Location::parse_id(id)
+                .filter(|_| !id.chars().any(char::is_control))
+                .filter(|location| {
+                    validate_place(tool, &location.name, &location.state, &location.country).is_ok()
+                })
+                .ok_or_else(|| invalid("'location_id' must be an ID listed by resolve-location"))?;
+            return Ok(LocationInput::Known(location));
+        }
+        match (self.lat, self.lon) {
+            (Some(lat), Some(lon)) => {
+                validate_coordinates(tool, lat, lon)?;
+                Ok(LocationInput::Coordinates { lat, lon })
+            }
+            _ => Err(invalid("'lat' and 'lon' must be given together")),
+        }
+    }
+}
+
+fn validate_coordinates(tool: &str, lat: f64, lon: f64) -> Result<(), WeatherError> {
+    if (-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon) {
+        return Ok(());
+    }
+    Err(WeatherError::InvalidArguments(format!(
+        "Invalid arguments for {}: 'lat' must be between -90 and 90 and 'lon' between -180 and 180.",
+        tool
+    )))
+}
+
+/// Rejects place queries that are empty, too long or contain control
+/// characters, along with malformed state and country qualifiers.
+fn validate_place(
+    tool: &str,
+    city: &str,
+    state: &Option<String>,
+    country: &Option<String>,
+) -> Result<(), WeatherError> {
+    validate_text(tool, "city", city)?;
+    if let Some(state) = state {
+        validate_text(tool, "state", state)?;
+    }
+    if let Some(country) = country {
+        validate_country(tool, country)?;
+    }
+    Ok(())
+}
+
+fn validate_country(tool: &str, country: &str) -> Result<(), WeatherError> {
+    let country = country.trim();
+    if country.len() == 2 && country.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Ok(());
+    }
+    Err(WeatherError::InvalidArguments(format!(
+        "Invalid arguments for {}: 'country' must be a two-letter ISO 3166 country code.",
+        tool
+    )))
+}
+
+fn validate_text(tool: &str, field: &str, value: &str) -> Result<(), WeatherError> {
+    let problem = if value.trim().is_empty() {
+        format!("'{}' must not be empty", field)
+    } else if value.chars().count() > MAX_PLACE_QUERY_CHARS {
+        format!(
+            "'{}' must be at most {} characters",
+            field, MAX_PLACE_QUERY_CHARS
+        )
+    } else if value.chars().any(char::is_control) {
+        format!("'{}' must not contain control characters", field)
+    } else {
+        return Ok(());
+    };
+    Err(WeatherError::InvalidArguments(format!(
+        "Invalid arguments for {}: {}.",
+        tool, problem
+    )))
+}
+
+/// Schema properties for [`LocationArgs`], shared by every tool that takes a
+/// location. The longest `location_id` is an 18-character point, three
+/// separators, a country code and a 100-character state and name.
+const LOCATION_PROPERTIES: &str = r#"
+{
+    "city": {
+        "type": "string",
+        "minLength": 1,
+        "maxLength": 100,
+        "description": "Place name, e.g. \"Paris\""
+    },
+    "state": {
+        "type": "string",
+        "maxLength": 100,
+        "description": "State, province or region, to tell apart places named 'city'"
+    },
+    "country": {
+        "type": "string",
+        "pattern": "^[A-Za-z]{2}$",
+        "description": "ISO 3166 two-letter country code; qualifies 'city', and is required with 'postal_code'"
+    },
+    "lat": {
+        "type": "number",
+        "minimum": -90,
+        "maximum": 90,
+        "description": "Latitude in decimal degrees, given with 'lon'; skips geocoding"
+    },
+    "lon": {
+        "type": "number",
+        "minimum": -180,
+        "maximum": 180,
+        "description": "Longitude in decimal degrees, given with 'lat'"
+    },
+    "postal_code": {
+        "type": "string",
+        "minLength": 1,
+        "maxLength": 100,
+        "description": "ZIP or postal code, given with 'country'"
+    },
+    "location_id": {
+        "type": "string",
+        "minLength": 1,
+        "maxLength": 223,
+        "description": "ID of a place listed by resolve-location"
+    }
+}
+"#;
+
+/// Adds the location properties to a tool's input `schema`, requiring
+/// exactly one way of naming the location.
+fn with_location_input(mut schema: Value) -> Value {
+    let properties: Value = serde_json::from_str(LOCATION_PROPERTIES).unwrap_or_default();
+    if let (Some(target), Value::Object(location)) = (
+        schema.get_mut("properties").and_then(Value::as_object_mut),
+        properties,
+    ) {
+        target.extend(location);
+    }
+    schema["oneOf"] = serde_json::json!([
+        { "required": ["city"] },
+        { "required": ["lat", "lon"] },
+        { "required": ["postal_code", "country"] },
+        { "required": ["location_id"] }
+    ]);
+    schema
+}
+
+/// Lists geocoding candidates as text, plus JSON carrying each one's ID.
+fn candidates_result(
+    place: &PlaceQuery,
+    candidates: &[Location],
+) -> Result<CallToolResult, ErrorData> {
+    let lines: Vec<String> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, location)| {
+            format!(
+                "{}. {} ({:.4}, {:.4}), location_id \"{}\"",
+                i + 1,
+                location.label(),
+                location.lat,
+                location.lon,
+                location.id()
+            )
+        })
+        .collect();
+    let candidates: Vec<Candidate> = candidates
+        .iter()
+        .map(|location| Candidate {
+            id: location.id(),
+            location,
+        })
+        .collect();
+    Ok(CallToolResult {
+        content: vec![
+            Content::text(format!(
+                "Locations matching '{}':\n{}",
+                place,
+                lines.join("\n")
+            )),
+            Content::json(serde_json::json!({ "candidates": candidates }))?,
+        ],
+        is_error: Some(false),
+    })
+}
+
+/// Scrubs registered secrets from everything a tool call sends back.
+fn redact_result(result: Result<CallToolResult, ErrorData>) -> Result<CallToolResult, ErrorData> {
+    match result {
+        Ok(mut result) => {
+            for content in &mut result.content {
+                if let RawContent::Text(text) = &mut content.raw {
+                    if let Cow::Owned(redacted) = redact::redact(&text.text) {
+                        text.text = redacted;
+                    }
+                }
+            }
+            Ok(result)
+        }
+        Err(mut error) => {
+            if let Cow::Owned(redacted) = redact::redact(&error.message) {
+                error.message = redacted.into();
+            }
+            if let Some(data) = &mut error.data {
+                redact::redact_json(data);
+            }
+            Err(error)
+        }
+    }
+}
+
+/// Deserializes tool arguments, describing what was `expected` on failure.
+fn parse_arguments<T: DeserializeOwned>(
+    tool: &str,
+    arguments: Option<Map<String, Value>>,
+    expected: &str,
+) -> Result<T, WeatherError> {
+    let reason = match arguments {
+        Some(args) => match serde_json::from_value(Value::Object(args)) {
+            Ok(params) => return Ok(params),
+            Err(e) => e.to_string(),
+        },
+        None => "missing arguments".to_string(),
+    };
+    Err(WeatherError::InvalidArguments(format!(
+        "Invalid arguments for {}: {}. Expected {}.",
+        tool, reason, expected
+    )))
+}
+
+impl ServerHandler for WeatherServerHandler {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some(format!(
+                "Native Weather MCP server backed by the {} provider",
+                self.provider.name()
+            )),
+            capabilities: ServerCapabilities {
+                tools: Some(ToolsCapability {
+                    list_changed: Some(true),
+                }),
+                ..Default::default()
+            },
+            ..Default::default()
+        }
+    }
+
+    async fn list_tools(
+        &self,
+        _request: PaginatedRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<ListToolsResult, ErrorData> {
+        let schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "candidates": {
+                        "type": "boolean",
+                        "description": "List the locations matching 'city' instead of fetching the weather"
+                    }
+                }
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let forecast_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "hours": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 120,
+                        "description": "Forecast horizon in hours (default 24)"
+                    }
+                }
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let air_quality_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "hours": {
+                        "type": "integer",
+                        "minimum": 0,
+                        "maximum": 96,
+                        "description": "Hours of hourly forecast to include after the current readings (default 0)"
+                    }
+                }
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let uv_pollen_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "days": {
+                        "type": "integer",
+                        "minimum": 1,
+                        "maximum": 4,
+                        "description": "Days to cover, starting today (default 1)"
+                    }
+                }
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let nowcast_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {}
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let history_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "date": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "A past day as YYYY-MM-DD"
+                    },
+                    "start_date": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "First day of a range as YYYY-MM-DD, with 'end_date'"
+                    },
+                    "end_date": {
+                        "type": "string",
+                        "format": "date",
+                        "description": "Last day of the range, at most 31 days after 'start_date' and before today"
+                    }
+                }
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let alerts_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {}
+            }
+            "#,
+        )
+        .map(with_location_input)
+        .unwrap_or_default();
+
+        let resolve_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "city": {
+                        "type": "string",
+                        "minLength": 1,
+                        "maxLength": 100,
+                        "description": "The place name to look up"
+                    },
+                    "state": {
+                        "type": "string",
+                        "maxLength": 100,
+                        "description": "State, province or region to narrow the search"
+                    },
+                    "country": {
+                        "type": "string",
+                        "pattern": "^[A-Za-z]{2}$",
+                        "description": "ISO 3166 two-letter country code to narrow the search"
+                    },
+                    "limit": {
+                        "type": "integer",
+                        "minimum": 1
+                    }
+                },
+                "required": ["city"]
+            }
+            "#,
+        )
+        .map(|mut schema: Value| {
+            let max = self.candidates_max();
+            schema["properties"]["limit"]["maximum"] = max.into();
+            schema["properties"]["limit"]["description"] = format!(
+                "Most candidates to return (default {})",
+                CANDIDATES_DEFAULT.min(max)
+            )
+            .into();
+            schema
+        })
+        .unwrap_or_default();
+
+        let reverse_schema: Value = serde_json::from_str(
+            r#"
+            {
+                "type": "object",
+                "properties": {
+                    "lat": {
+                        "type": "number",
+                        "minimum": -90,
+                        "maximum": 90,
+                        "description": "Latitude in decimal degrees"
+                    },
+                    "lon": {
+                        "type": "number",
+                        "minimum": -180,
+                        "maximum": 180,
+                        "description": "Longitude in decimal degrees"
+                    }
+                },
+                "required": ["lat", "lon"]
+            }
+            "#,
+        )
+        .unwrap_or_default();
+
+        let tools = vec![
+            Tool {
+                name: "get-weather".into(),
+                description: "Get the current weather for a given city, with temperature, humidity, wind and conditions.".into(),
+                input_schema: Arc::new(schema.as_object().unwrap_or(&Map::new()).clone()),
+            },
+            Tool {
+                name: "get-forecast".into(),
+                description: "Get the forecast for a given city for up to 5 days ahead, with temperature, precipitation probability, wind and conditions per time step.".into(),
+                input_schema: Arc::new(
+                    forecast_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "get-air-quality".into(),
+                description: "Get the air quality index with its category and the PM2.5, PM10, O3, NO2, SO2 and CO concentrations for a location, currently and optionally as an hourly forecast.".into(),
+                input_schema: Arc::new(
+                    air_quality_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "get-uv-pollen".into(),
+                description: "Get the daily peak UV index and grass, tree and weed pollen counts for a location, each with its risk category and the local time of the peak. Pollen is only forecast for Europe.".into(),
+                input_schema: Arc::new(
+                    uv_pollen_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "get-nowcast".into(),
+                description: "Get precipitation intensity for the next 60 minutes at a location, minute by minute where the provider supports it, with a summary of when precipitation starts and how long it lasts.".into(),
+                input_schema: Arc::new(
+                    nowcast_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "get-historical-weather".into(),
+                description: "Get the weather on past days for a location: daily minimum, maximum and mean temperature, total precipitation and the prevailing condition, for one date or a range of up to 31 days.".into(),
+                input_schema: Arc::new(
+                    history_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "get-alerts".into(),
+                description: "Get the official weather warnings and advisories in effect at a location, most severe first, with event, severity, onset and expiry times and instructions. Alerts come from the US National Weather Service, so only US locations are covered.".into(),
+                input_schema: Arc::new(alerts_schema.as_object().unwrap_or(&Map::new()).clone()),
+            },
+            Tool {
+                name: "resolve-location".into(),
+                description: "List the places matching a name, with state, country, coordinates and an ID. Pass the ID to get-weather as 'location_id' to choose between places that share a name.".into(),
+                input_schema: Arc::new(
+                    resolve_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+            Tool {
+                name: "reverse-geocode".into(),
+                description: "Name the place at the given coordinates, with its state, country and a location ID for the other tools.".into(),
+                input_schema: Arc::new(
+                    reverse_schema.as_object().unwrap_or(&Map::new()).clone(),
+                ),
+            },
+        ];
+
+        Ok(ListToolsResult {
+            tools,
+            next_cursor: None,
+        })
+    }
+
+    #[tracing::instrument(skip_all, fields(tool = %request.name))]
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        _context: RequestContext<RoleServer>,
+    ) -> Result<CallToolResult, ErrorData> {
+        let started = Instant::now();
+        let result = redact_result(self.dispatch_tool(request).await);
+        let elapsed_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(CallToolResult {
+                is_error: Some(true),
+                content,
+            }) => {
+                let message = content
+                    .first()
+                    .and_then(|c| c.raw.as_text())
+                    .map(|t| t.text.as_str())
+                    .unwrap_or_default();
+                tracing::warn!(elapsed_ms, error = message, "tool call failed");
+            }
+            Ok(_) => tracing::info!(elapsed_ms, "tool call succeeded"),
+            Err(e) => tracing::warn!(elapsed_ms, error = %e.message, "tool call rejected"),
+        }
+        result
+    }
+}
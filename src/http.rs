@@ -0,0 +1,30 @@
+use std::time::Duration;
+
+use crate::config::HttpConfig;
+
+// Some upstreams (met.no in particular) reject requests without an
+// identifying User-Agent.
+const USER_AGENT: &str = concat!(env!("CARGO_PKG_NAME"), "/", env!("CARGO_PKG_VERSION"));
+
+const POOL_IDLE_TIMEOUT: Duration = Duration::from_secs(90);
+
+/// Builds the HTTP client shared by every provider.
+///
+/// `reqwest::Client` is a handle around a connection pool, so clones are
+/// cheap and reuse the same connections, TLS sessions and HTTP/2 streams.
+pub fn client(config: &HttpConfig) -> Result<reqwest::Client, String> {
+    reqwest::Client::builder()
+        .user_agent(USER_AGENT)
+        .timeout(config.timeout)
+        .connect_timeout(config.connect_timeout)
+        .pool_max_idle_per_host(config.pool_max_idle_per_host)
+        .pool_idle_timeout(POOL_IDLE_TIMEOUT)
+        .gzip(true)
+        .build()
+        .map_err(|e| format!("Failed to build HTTP client: {}", e))
+}
+
+/// A client with the default tuning, for providers built without one.
+pub(crate) fn default_client() -> reqwest::Client {
+    client(&HttpConfig::default()).expect("default HTTP client configuration is valid")
+}
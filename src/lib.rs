@@ -0,0 +1,9 @@
+pub mod config;
+pub mod error;
+pub mod geo;
+pub mod handler;
+pub mod http;
+pub mod logging;
+pub mod model;
+pub mod provider;
+pub mod redact;
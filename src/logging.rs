@@ -0,0 +1,59 @@
+use std::{fs::OpenOptions, sync::Mutex};
+use tracing_subscriber::{
+    fmt::writer::BoxMakeWriter, layer::SubscriberExt, util::SubscriberInitExt, EnvFilter, Layer,
+};
+
+use crate::config::{LogConfig, LogFormat};
+use crate::redact::RedactingMakeWriter;
+
+/// Installs the global tracing subscriber.
+///
+/// Logs go to stderr unless a log file is configured. Stdout carries the MCP
+/// stdio transport, so nothing here may ever write to it. Every line is
+/// passed through [`crate::redact`] first.
+pub fn init(config: &LogConfig) -> Result<(), String> {
+    let filter = EnvFilter::try_new(&config.filter)
+        .map_err(|e| format!("Invalid log filter '{}': {}", config.filter, e))?;
+
+    let (writer, ansi) = match &config.file {
+        Some(path) => {
+            let file = OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(|e| format!("Failed to open log file {}: {}", path.display(), e))?;
+            (
+                BoxMakeWriter::new(RedactingMakeWriter::new(Mutex::new(file))),
+                false,
+            )
+        }
+        None => (
+            BoxMakeWriter::new(RedactingMakeWriter::new(std::io::stderr)),
+            true,
+        ),
+    };
+
+    let layer = match config.format {
+        LogFormat::Pretty => tracing_subscriber::fmt::layer()
+            .pretty()
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Compact => tracing_subscriber::fmt::layer()
+            .compact()
+            .with_ansi(ansi)
+            .with_writer(writer)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_current_span(true)
+            .with_writer(writer)
+            .boxed(),
+    };
+
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(layer)
+        .try_init()
+        .map_err(|e| format!("Failed to initialize logging: {}", e))
+}
@@ -0,0 +1,682 @@
+use serde::{Deserialize, Serialize};
+
+/// A geocoded place.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+    pub name: String,
+    pub country: Option<String>,
+    pub state: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+}
+
+impl Location {
+    /// A location known only by its coordinates, named after them.
+    pub fn at(lat: f64, lon: f64) -> Self {
+        Location {
+            name: format!("{:.4}, {:.4}", lat, lon),
+            country: None,
+            state: None,
+            lat,
+            lon,
+        }
+    }
+
+    /// The coordinates rounded to four decimal places, roughly 10 m, e.g.
+    /// "39.7990,-89.6440".
+    pub fn point(&self) -> String {
+        format!("{:.4},{:.4}", self.lat, self.lon)
+    }
+
+    /// Stable identifier for the location that callers can pass back to look
+    /// it up again. It encodes the [`point`](Self::point) followed by the
+    /// country, state and name, e.g. "39.7990,-89.6440;US;Illinois;Springfield",
+    /// so no geocoding is needed.
+    pub fn id(&self) -> String {
+        format!(
+            "{};{};{};{}",
+            self.point(),
+            self.country.as_deref().unwrap_or_default(),
+            self.state.as_deref().unwrap_or_default(),
+            self.name
+        )
+    }
+
+    /// The location encoded in an [`id`](Self::id), if `id` is one.
+    ///
+    /// The result equals the original location but for its coordinates, which
+    /// are rounded to the point's precision. A bare point is accepted too and
+    /// named after its coordinates, like [`Location::at`].
+    pub fn parse_id(id: &str) -> Option<Location> {
+        let (point, label) = match id.trim().split_once(';') {
+            Some((point, label)) => (point, Some(label)),
+            None => (id.trim(), None),
+        };
+        let (lat, lon) = point.split_once(',')?;
+        let lat: f64 = lat.trim().parse().ok()?;
+        let lon: f64 = lon.trim().parse().ok()?;
+        if !((-90.0..=90.0).contains(&lat) && (-180.0..=180.0).contains(&lon)) {
+            return None;
+        }
+        let Some(label) = label else {
+            return Some(Location::at(lat, lon));
+        };
+
+        // The name comes last, so it may contain the separator itself.
+        let mut parts = label.splitn(3, ';');
+        let (country, state, name) = (parts.next()?, parts.next()?, parts.next()?);
+        let part = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        Some(Location {
+            name: part(name)?,
+            country: part(country),
+            state: part(state),
+            lat,
+            lon,
+        })
+    }
+
+    /// The name qualified by state and country, e.g. "Springfield, Illinois, US".
+    pub fn label(&self) -> String {
+        [Some(&self.name), self.state.as_ref(), self.country.as_ref()]
+            .into_iter()
+            .flatten()
+            .map(String::as_str)
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+}
+
+/// A place name, optionally narrowed down by state and country.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PlaceQuery {
+    pub name: String,
+    /// State, province or region, as named by the provider.
+    pub state: Option<String>,
+    /// ISO 3166-1 alpha-2 country code.
+    pub country: Option<String>,
+}
+
+impl PlaceQuery {
+    pub fn new(name: impl Into<String>) -> Self {
+        PlaceQuery {
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    /// Whether `location` lies in the requested country, if one was given.
+    pub fn matches_country(&self, location: &Location) -> bool {
+        match (&self.country, &location.country) {
+            (None, _) => true,
+            (Some(wanted), Some(country)) => wanted.trim().eq_ignore_ascii_case(country),
+            (Some(_), None) => false,
+        }
+    }
+
+    /// Whether `location` lies in the requested state, if one was given.
+    pub fn matches_state(&self, location: &Location) -> bool {
+        match (&self.state, &location.state) {
+            (None, _) => true,
+            (Some(wanted), Some(state)) => wanted.trim().eq_ignore_ascii_case(state),
+            (Some(_), None) => false,
+        }
+    }
+}
+
+/// How a tool call names the location it is about.
+#[derive(Debug, Clone, PartialEq)]
+pub enum LocationInput {
+    /// A place name, resolved through the provider's geocoder.
+    Place(PlaceQuery),
+    /// Decimal degrees, used as given.
+    Coordinates { lat: f64, lon: f64 },
+    /// A location decoded from its ID, used as given.
+    Known(Location),
+    /// A postal code within an ISO 3166-1 alpha-2 country.
+    PostalCode { code: String, country: String },
+}
+
+impl std::fmt::Display for LocationInput {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LocationInput::Place(place) => place.fmt(f),
+            LocationInput::Coordinates { lat, lon } => write!(f, "{:.4}, {:.4}", lat, lon),
+            LocationInput::Known(location) => f.write_str(&location.label()),
+            LocationInput::PostalCode { code, country } => {
+                write!(f, "{}, {}", code.trim(), country.trim())
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for PlaceQuery {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name.trim())?;
+        for qualifier in [&self.state, &self.country].into_iter().flatten() {
+            write!(f, ", {}", qualifier.trim())?;
+        }
+        Ok(())
+    }
+}
+
+/// A current-conditions observation, independent of the upstream payload shape.
+///
+/// Temperatures are in °C, speeds in m/s, pressure in hPa, visibility in metres
+/// and precipitation volumes in mm. Timestamps are Unix seconds (UTC).
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Observation {
+    pub location: String,
+    pub country: Option<String>,
+    pub lat: f64,
+    pub lon: f64,
+    pub observed_at: i64,
+    pub temperature: Temperature,
+    pub humidity: Option<f64>,
+    pub pressure: Option<f64>,
+    pub wind: Wind,
+    pub clouds: Option<f64>,
+    pub visibility: Option<f64>,
+    pub rain: Precipitation,
+    pub snow: Precipitation,
+    pub conditions: Vec<Condition>,
+    pub sunrise: Option<i64>,
+    pub sunset: Option<i64>,
+    /// Offset of the location's local time from UTC, in seconds.
+    pub timezone_offset: Option<i32>,
+    /// Set when the provider could not be reached and this is the last
+    /// observation cached for the location.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub stale: bool,
+    /// Seconds between `observed_at` and when a stale observation was served.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub age_secs: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Temperature {
+    pub current: f64,
+    pub feels_like: Option<f64>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Wind {
+    pub speed: Option<f64>,
+    /// Meteorological direction the wind blows from, in degrees.
+    pub direction: Option<f64>,
+    pub gust: Option<f64>,
+}
+
+/// Precipitation volume over the last one and three hours.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Precipitation {
+    pub last_1h: Option<f64>,
+    pub last_3h: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Condition {
+    /// OpenWeatherMap condition code (https://openweathermap.org/weather-conditions).
+    /// Other providers map their own codes onto the closest equivalent.
+    pub code: u32,
+    pub main: String,
+    pub description: String,
+    pub icon: Option<String>,
+}
+
+impl Observation {
+    /// One-paragraph human-readable rendering of the observation.
+    pub fn summary(&self) -> String {
+        let mut summary = format!(
+            "The temperature in {} is {}°C",
+            self.location, self.temperature.current
+        );
+        if let Some(feels_like) = self.temperature.feels_like {
+            summary.push_str(&format!(" (feels like {}°C)", feels_like));
+        }
+        if let Some(condition) = self.conditions.first() {
+            summary.push_str(&format!(", {}", condition.description));
+        }
+        summary.push('.');
+        if let Some(humidity) = self.humidity {
+            summary.push_str(&format!(" Humidity {}%.", humidity));
+        }
+        if let Some(pressure) = self.pressure {
+            summary.push_str(&format!(" Pressure {} hPa.", pressure));
+        }
+        if let Some(speed) = self.wind.speed {
+            summary.push_str(&format!(" Wind {} m/s", speed));
+            if let Some(direction) = self.wind.direction {
+                summary.push_str(&format!(" from {}°", direction));
+            }
+            if let Some(gust) = self.wind.gust {
+                summary.push_str(&format!(", gusting {} m/s", gust));
+            }
+            summary.push('.');
+        }
+        if let Some(rain) = self.rain.last_1h.or(self.rain.last_3h) {
+            summary.push_str(&format!(" Rain {} mm.", rain));
+        }
+        if let Some(snow) = self.snow.last_1h.or(self.snow.last_3h) {
+            summary.push_str(&format!(" Snow {} mm.", snow));
+        }
+        if self.stale {
+            summary.push_str(
+                " The weather service is currently unavailable, \
+                 so this is the last known observation",
+            );
+            if let Some(age) = self.age_secs {
+                summary.push_str(&format!(", from {} ago", format_age(age)));
+            }
+            summary.push('.');
+        }
+        summary
+    }
+
+    /// Flags a cached observation served in place of a failed lookup,
+    /// recording its age as of `now` (Unix seconds).
+    pub fn into_stale(mut self, now: i64) -> Self {
+        self.stale = true;
+        self.age_secs = Some(now.saturating_sub(self.observed_at).max(0) as u64);
+        self
+    }
+}
+
+fn format_age(secs: u64) -> String {
+    match secs {
+        0..=119 => format!("{} seconds", secs),
+        120..=7199 => format!("{} minutes", secs / 60),
+        7200..=172_799 => format!("{} hours", secs / 3600),
+        _ => format!("{} days", secs / 86_400),
+    }
+}
+
+/// One 3-hour bucket of the forecast, as returned to the client.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ForecastEntry {
+    pub time: String,
+    pub temp: f64,
+    pub precipitation_probability: f64,
+    pub wind_speed: f64,
+    pub wind_deg: Option<f64>,
+    pub conditions: String,
+}
+
+/// The day's UV index and pollen peaks, in the location's local time.
+/// Pollen counts are only forecast for Europe; elsewhere they are `None`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UvPollenDay {
+    /// ISO 8601 date, e.g. "2024-05-01".
+    pub date: String,
+    pub uv: Option<Exposure>,
+    /// Pollen concentrations in grains/m³; trees are alder, birch and olive,
+    /// weeds mugwort and ragweed.
+    pub grass: Option<Exposure>,
+    pub tree: Option<Exposure>,
+    pub weed: Option<Exposure>,
+}
+
+/// The highest reading of one hazard during a day.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Exposure {
+    pub peak: f64,
+    /// Local time of the peak, e.g. "13:00".
+    pub peak_time: String,
+    /// What `peak` means for health, e.g. "High".
+    pub risk: String,
+}
+
+/// The kinds of pollen reported, each graded on its own scale.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollenKind {
+    Grass,
+    Tree,
+    Weed,
+}
+
+impl Exposure {
+    /// A UV index peak, graded on the WHO scale.
+    pub fn uv(peak: f64, peak_time: String) -> Self {
+        let risk = match peak {
+            i if i < 3.0 => "Low",
+            i if i < 6.0 => "Moderate",
+            i if i < 8.0 => "High",
+            i if i < 11.0 => "Very high",
+            _ => "Extreme",
+        };
+        Exposure {
+            peak,
+            peak_time,
+            risk: risk.to_string(),
+        }
+    }
+
+    /// A pollen peak in grains/m³, graded with the US National Allergy
+    /// Bureau's thresholds for `kind`.
+    pub fn pollen(kind: PollenKind, peak: f64, peak_time: String) -> Self {
+        let (moderate, high, very_high) = match kind {
+            PollenKind::Grass => (5.0, 20.0, 200.0),
+            PollenKind::Tree => (15.0, 90.0, 1500.0),
+            PollenKind::Weed => (10.0, 50.0, 500.0),
+        };
+        let risk = match peak {
+            p if p < 1.0 => "None",
+            p if p < moderate => "Low",
+            p if p < high => "Moderate",
+            p if p < very_high => "High",
+            _ => "Very high",
+        };
+        Exposure {
+            peak,
+            peak_time,
+            risk: risk.to_string(),
+        }
+    }
+}
+
+impl UvPollenDay {
+    /// One-line rendering, e.g. "2024-05-01: UV 6.1 (High) at 13:00; grass
+    /// 35 grains/m³ (High) at 15:00; ...".
+    pub fn summary(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(uv) = &self.uv {
+            parts.push(format!(
+                "UV {:.1} ({}) at {}",
+                uv.peak, uv.risk, uv.peak_time
+            ));
+        }
+        for (name, pollen) in [
+            ("grass", &self.grass),
+            ("tree", &self.tree),
+            ("weed", &self.weed),
+        ] {
+            if let Some(pollen) = pollen {
+                parts.push(format!(
+                    "{} {:.0} grains/m³ ({}) at {}",
+                    name, pollen.peak, pollen.risk, pollen.peak_time
+                ));
+            }
+        }
+        if parts.is_empty() {
+            parts.push("no data".to_string());
+        }
+        format!("{}: {}", self.date, parts.join("; "))
+    }
+
+    pub fn has_pollen(&self) -> bool {
+        self.grass.is_some() || self.tree.is_some() || self.weed.is_some()
+    }
+}
+
+/// Precipitation intensity, at the provider's finest resolution, for about
+/// the next hour.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Nowcast {
+    /// Minutes between steps: 1 where the provider has minute-level data.
+    pub resolution_minutes: u32,
+    pub steps: Vec<NowcastStep>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct NowcastStep {
+    /// Start of the step, in Unix seconds.
+    pub time: i64,
+    /// Precipitation rate in mm/h.
+    pub intensity: f64,
+}
+
+/// Rates below this many mm/h count as dry.
+const NOWCAST_WET_MM_PER_HOUR: f64 = 0.1;
+
+impl Nowcast {
+    /// Drops the steps that ended before `now` (Unix seconds) or start more
+    /// than `minutes` after it, so a cached nowcast still reads from now.
+    pub fn upcoming(mut self, now: i64, minutes: u32) -> Self {
+        let step = i64::from(self.resolution_minutes) * 60;
+        let horizon = now + i64::from(minutes) * 60;
+        self.steps
+            .retain(|s| s.time + step > now && s.time < horizon);
+        self
+    }
+
+    /// When the first spell of precipitation after `now` starts and how long
+    /// it lasts, e.g. "Light precipitation starting in ~12 minutes, lasting
+    /// ~20 minutes".
+    pub fn summary(&self, now: i64) -> String {
+        let offset = |time: i64| ((time - now) as f64 / 60.0).round().max(0.0) as i64;
+        let horizon = self
+            .steps
+            .last()
+            .map_or(0, |s| offset(s.time) + i64::from(self.resolution_minutes));
+        let wet = |s: &&NowcastStep| s.intensity >= NOWCAST_WET_MM_PER_HOUR;
+
+        let Some(first) = self.steps.iter().position(|s| wet(&s)) else {
+            return format!("No precipitation expected in the next {} minutes", horizon);
+        };
+        let spell: Vec<&NowcastStep> = self.steps[first..].iter().take_while(wet).collect();
+        let peak = spell.iter().map(|s| s.intensity).fold(0.0, f64::max);
+        let start = offset(self.steps[first].time);
+        let end = self.steps.get(first + spell.len()).map(|s| offset(s.time));
+
+        let kind = format!("{} precipitation", intensity_label(peak));
+        match (start, end) {
+            (0, Some(end)) => format!("{} now, stopping in ~{} minutes", kind, end),
+            (0, None) => format!("{} now, continuing for at least {} minutes", kind, horizon),
+            (start, Some(end)) => format!(
+                "{} starting in ~{} minutes, lasting ~{} minutes",
+                kind,
+                start,
+                end - start
+            ),
+            (start, None) => format!(
+                "{} starting in ~{} minutes, lasting at least {} minutes",
+                kind,
+                start,
+                horizon - start
+            ),
+        }
+    }
+}
+
+/// Describes a precipitation rate in mm/h the way the AMS glossary grades
+/// rain.
+fn intensity_label(mm_per_hour: f64) -> &'static str {
+    match mm_per_hour {
+        r if r < 2.5 => "Light",
+        r if r < 7.6 => "Moderate",
+        r if r < 50.0 => "Heavy",
+        _ => "Violent",
+    }
+}
+
+/// Aggregates for one past day, in the location's local time.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DailyWeather {
+    /// ISO 8601 date, e.g. "2024-01-01".
+    pub date: String,
+    pub temp_min: f64,
+    pub temp_max: f64,
+    pub temp_mean: f64,
+    /// Total precipitation in millimetres, snow included as water.
+    pub precipitation: f64,
+    /// The condition reported for the most hours of the day.
+    pub conditions: String,
+}
+
+impl DailyWeather {
+    /// One-line rendering, e.g. "2024-01-01: 3.1 to 8.4°C (mean 5.6°C),
+    /// 4.2 mm precipitation, mostly light rain".
+    pub fn summary(&self) -> String {
+        format!(
+            "{}: {:.1} to {:.1}°C (mean {:.1}°C), {:.1} mm precipitation, mostly {}",
+            self.date,
+            self.temp_min,
+            self.temp_max,
+            self.temp_mean,
+            self.precipitation,
+            self.conditions
+        )
+    }
+}
+
+/// Air quality at one point in time. Pollutant concentrations are in µg/m³.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirQuality {
+    pub time: String,
+    pub aqi: u32,
+    pub scale: AqiScale,
+    /// What `aqi` means on its scale, e.g. "Moderate".
+    pub category: String,
+    pub pm2_5: Option<f64>,
+    pub pm10: Option<f64>,
+    pub o3: Option<f64>,
+    pub no2: Option<f64>,
+    pub so2: Option<f64>,
+    pub co: Option<f64>,
+}
+
+/// The index an [`AirQuality::aqi`] is expressed on; providers use different
+/// ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AqiScale {
+    /// OpenWeatherMap's index, from 1 (good) to 5 (very poor).
+    Openweathermap,
+    /// The US EPA's index, from 0 to 500.
+    UsEpa,
+}
+
+impl AqiScale {
+    /// The category label for `aqi` on this scale.
+    pub fn category(self, aqi: u32) -> &'static str {
+        match self {
+            AqiScale::Openweathermap => match aqi {
+                0 | 1 => "Good",
+                2 => "Fair",
+                3 => "Moderate",
+                4 => "Poor",
+                _ => "Very Poor",
+            },
+            AqiScale::UsEpa => match aqi {
+                0..=50 => "Good",
+                51..=100 => "Moderate",
+                101..=150 => "Unhealthy for Sensitive Groups",
+                151..=200 => "Unhealthy",
+                201..=300 => "Very Unhealthy",
+                _ => "Hazardous",
+            },
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            AqiScale::Openweathermap => "OpenWeatherMap 1-5",
+            AqiScale::UsEpa => "US EPA 0-500",
+        }
+    }
+}
+
+impl AirQuality {
+    /// One-line rendering, e.g. "AQI 2 (Fair, OpenWeatherMap 1-5 scale);
+    /// PM2.5 3.2, PM10 4.1 µg/m³".
+    pub fn summary(&self) -> String {
+        let pollutants: Vec<String> = [
+            ("PM2.5", self.pm2_5),
+            ("PM10", self.pm10),
+            ("O3", self.o3),
+            ("NO2", self.no2),
+            ("SO2", self.so2),
+            ("CO", self.co),
+        ]
+        .into_iter()
+        .filter_map(|(name, value)| value.map(|v| format!("{} {}", name, v)))
+        .collect();
+
+        let mut summary = format!(
+            "AQI {} ({}, {} scale)",
+            self.aqi,
+            self.category,
+            self.scale.label()
+        );
+        if !pollutants.is_empty() {
+            summary.push_str(&format!("; {} µg/m³", pollutants.join(", ")));
+        }
+        summary
+    }
+}
+
+/// Current air quality, followed by hourly forecasts if any were requested.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AirQualityReport {
+    pub current: AirQuality,
+    pub forecast: Vec<AirQuality>,
+}
+
+/// An official warning or advisory in effect at a location.
+///
+/// Times are ISO 8601 with the issuer's UTC offset.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Alert {
+    pub event: String,
+    /// "Extreme", "Severe", "Moderate", "Minor" or "Unknown".
+    pub severity: String,
+    pub headline: Option<String>,
+    /// The areas the issuer lists, in words.
+    pub area: Option<String>,
+    pub onset: Option<String>,
+    pub expires: Option<String>,
+    pub description: Option<String>,
+    /// What people in the area should do.
+    pub instruction: Option<String>,
+    pub sender: Option<String>,
+    pub matched_by: AlertMatch,
+}
+
+/// How an alert was found to cover the requested location.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertMatch {
+    /// The alert's polygon contains the point.
+    Polygon,
+    /// The alert has no polygon; the issuer matched it to the point's
+    /// forecast zone.
+    Zone,
+}
+
+impl Alert {
+    /// Ranks severities from most to least severe, for sorting.
+    pub fn severity_rank(&self) -> u8 {
+        match self.severity.as_str() {
+            "Extreme" => 0,
+            "Severe" => 1,
+            "Moderate" => 2,
+            "Minor" => 3,
+            _ => 4,
+        }
+    }
+
+    /// Multi-line rendering: severity, event and validity, then headline and
+    /// instructions.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("[{}] {}", self.severity, self.event);
+        match (&self.onset, &self.expires) {
+            (Some(onset), Some(expires)) => {
+                summary.push_str(&format!(", from {} until {}", onset, expires))
+            }
+            (Some(onset), None) => summary.push_str(&format!(", from {}", onset)),
+            (None, Some(expires)) => summary.push_str(&format!(", until {}", expires)),
+            (None, None) => {}
+        }
+        if let Some(headline) = &self.headline {
+            summary.push_str(&format!("\n  {}", headline));
+        }
+        if let Some(instruction) = &self.instruction {
+            summary.push_str(&format!(
+                "\n  Instructions: {}",
+                instruction.split_whitespace().collect::<Vec<_>>().join(" ")
+            ));
+        }
+        summary
+    }
+}
@@ -0,0 +1,659 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    borrow::Borrow,
+    collections::{HashMap, HashSet},
+    future::Future,
+    hash::Hash,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+use super::single_flight::SingleFlight;
+use super::{DiskCache, WeatherProvider};
+use crate::config::CacheConfig;
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Alert, DailyWeather, ForecastEntry, Location, Nowcast, Observation,
+    PlaceQuery, UvPollenDay,
+};
+
+/// Nowcasts describe the next hour minute by minute, so they are kept for at
+/// most this long whatever the weather TTL.
+const NOWCAST_MAX_TTL: Duration = Duration::from_secs(2 * 60);
+
+/// Wraps another provider with in-memory TTL caches, optionally backed by a
+/// persistent [`DiskCache`].
+///
+/// Geocoding results and weather lookups are cached separately so place
+/// names can be remembered for days while observations expire in minutes.
+/// Memory is checked first, then disk; fresh upstream results are written to
+/// both. Concurrent misses for the same key share one upstream request.
+/// Errors are never cached.
+///
+/// When the provider fails with a retryable error, expired geocoding results
+/// and observations are served instead for up to `max_stale`, observations
+/// flagged with [`Observation::into_stale`]. A background task then refreshes
+/// the entry every `refresh_interval` until the provider recovers, and further
+/// lookups for it are answered from the stale entry straight away rather than
+/// waiting on the failing provider again.
+pub struct CachingProvider {
+    inner: Arc<dyn WeatherProvider>,
+    store: Option<Arc<DiskCache>>,
+    refresh_interval: Duration,
+    max_stale: Duration,
+    geocode: Arc<Lookups<Location>>,
+    candidates: Arc<Lookups<Vec<Location>>>,
+    postal_codes: Arc<Lookups<Location>>,
+    reverse: Arc<Lookups<Location>>,
+    current: Arc<Lookups<Observation>>,
+    forecast: Arc<Lookups<Vec<ForecastEntry>>>,
+    air_quality: Arc<Lookups<AirQualityReport>>,
+    alerts: Arc<Lookups<Vec<Alert>>>,
+    history: Arc<Lookups<Vec<DailyWeather>>>,
+    nowcasts: Arc<Lookups<Nowcast>>,
+    uv_pollen: Arc<Lookups<Vec<UvPollenDay>>>,
+}
+
+/// The memory cache, in-flight requests and background refreshes for one
+/// kind of lookup.
+struct Lookups<V> {
+    memory: TtlCache<String, V>,
+    flights: SingleFlight<V>,
+    refreshing: Mutex<HashSet<String>>,
+    /// Annotates an expired value before it is served, or `None` if this kind
+    /// is never served stale.
+    mark_stale: Option<fn(V) -> V>,
+}
+
+impl<V: Clone> Lookups<V> {
+    fn new(
+        name: &'static str,
+        ttl: Duration,
+        config: &CacheConfig,
+        mark_stale: Option<fn(V) -> V>,
+    ) -> Arc<Self> {
+        let retention = if mark_stale.is_some() {
+            config.max_stale
+        } else {
+            Duration::ZERO
+        };
+        Arc::new(Lookups {
+            memory: TtlCache::new(name, ttl, retention, config.max_entries),
+            flights: SingleFlight::new(),
+            refreshing: Mutex::new(HashSet::new()),
+            mark_stale,
+        })
+    }
+
+    fn is_refreshing(&self, key: &str) -> bool {
+        self.refreshing.lock().unwrap().contains(key)
+    }
+}
+
+/// Counters for one cache since startup.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    /// Memory misses answered by the persistent store. Also counted in
+    /// `misses`.
+    pub disk_hits: u64,
+    pub misses: u64,
+    /// Misses that joined an identical in-flight request instead of sending
+    /// their own. Also counted in `misses`.
+    pub coalesced: u64,
+    /// Expired entries served because the provider was unavailable.
+    pub stale: u64,
+    pub evictions: u64,
+    pub entries: usize,
+}
+
+/// Counters for each of a [`CachingProvider`]'s caches.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct CacheMetrics {
+    pub geocode: CacheStats,
+    pub candidates: CacheStats,
+    pub postal_codes: CacheStats,
+    pub reverse: CacheStats,
+    pub current: CacheStats,
+    pub forecast: CacheStats,
+    pub air_quality: CacheStats,
+    pub alerts: CacheStats,
+    pub history: CacheStats,
+    pub nowcasts: CacheStats,
+    pub uv_pollen: CacheStats,
+}
+
+impl CachingProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>, config: &CacheConfig) -> Self {
+        CachingProvider {
+            inner,
+            store: None,
+            refresh_interval: config.refresh_interval,
+            max_stale: config.max_stale,
+            geocode: Lookups::new("geocode", config.geocode_ttl, config, Some(|l| l)),
+            candidates: Lookups::new("candidates", config.geocode_ttl, config, Some(|l| l)),
+            postal_codes: Lookups::new("postal_codes", config.geocode_ttl, config, Some(|l| l)),
+            reverse: Lookups::new("reverse", config.geocode_ttl, config, Some(|l| l)),
+            current: Lookups::new(
+                "current",
+                config.weather_ttl,
+                config,
+                Some(|o: Observation| o.into_stale(chrono::Utc::now().timestamp())),
+            ),
+            // Past forecast steps are worse than no answer, so forecasts, and
+            // the air quality and pollen reports which carry them, are never
+            // served stale.
+            forecast: Lookups::new("forecast", config.weather_ttl, config, None),
+            air_quality: Lookups::new("air_quality", config.weather_ttl, config, None),
+            uv_pollen: Lookups::new("uv_pollen", config.weather_ttl, config, None),
+            // An alert that has since been cancelled must not be repeated.
+            alerts: Lookups::new("alerts", config.weather_ttl, config, None),
+            // Past days don't change, so history keeps as long as place names.
+            history: Lookups::new("history", config.geocode_ttl, config, Some(|h| h)),
+            // Each minute cached is a minute cut off the end of the hour.
+            nowcasts: Lookups::new(
+                "nowcasts",
+                config.weather_ttl.min(NOWCAST_MAX_TTL),
+                config,
+                None,
+            ),
+        }
+    }
+
+    /// Persists entries to `store` so they survive restarts.
+    pub fn with_store(mut self, store: impl Into<Arc<DiskCache>>) -> Self {
+        self.store = Some(store.into());
+        self
+    }
+
+    pub fn metrics(&self) -> CacheMetrics {
+        CacheMetrics {
+            geocode: self.geocode.memory.stats(),
+            candidates: self.candidates.memory.stats(),
+            postal_codes: self.postal_codes.memory.stats(),
+            reverse: self.reverse.memory.stats(),
+            current: self.current.memory.stats(),
+            forecast: self.forecast.memory.stats(),
+            air_quality: self.air_quality.memory.stats(),
+            alerts: self.alerts.memory.stats(),
+            history: self.history.memory.stats(),
+            nowcasts: self.nowcasts.memory.stats(),
+            uv_pollen: self.uv_pollen.memory.stats(),
+        }
+    }
+
+    /// Answers from the cache, or runs `fetch` and caches its result. `fetch`
+    /// may be called again later to refresh a stale entry in the background.
+    async fn cached<V, F, Fut>(
+        &self,
+        lookups: &Arc<Lookups<V>>,
+        key: String,
+        fetch: F,
+    ) -> Result<V, WeatherError>
+    where
+        V: Clone + Serialize + DeserializeOwned + Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<V, WeatherError>> + Send + 'static,
+    {
+        let cache = &lookups.memory;
+        if let Some(value) = cache.get(&key) {
+            return Ok(value);
+        }
+        if lookups.is_refreshing(&key) {
+            if let Some(value) = self.stale(lookups, &key) {
+                return Ok(value);
+            }
+        }
+
+        let store = self.store.as_deref().filter(|_| cache.enabled());
+        let (result, joined) = lookups
+            .flights
+            .run(&key, async {
+                if let Some((value, ttl)) = store.and_then(|s| s.get::<V>(cache.name, &key)) {
+                    cache.record_disk_hit();
+                    cache.insert_for(key.clone(), value.clone(), ttl);
+                    return Ok(value);
+                }
+
+                let value = fetch().await?;
+                remember(store, cache, &key, &value);
+                Ok(value)
+            })
+            .await;
+        if joined {
+            cache.record_coalesced();
+        }
+
+        match result {
+            Err(e) if e.is_retryable() => match self.stale(lookups, &key) {
+                Some(value) => {
+                    tracing::warn!(
+                        cache = cache.name,
+                        error = %e,
+                        "provider unavailable, serving stale entry"
+                    );
+                    self.spawn_refresh(lookups, key, fetch);
+                    Ok(value)
+                }
+                None => Err(e),
+            },
+            result => result,
+        }
+    }
+
+    /// The expired entry for `key`, annotated as stale, if one is still kept.
+    fn stale<V>(&self, lookups: &Lookups<V>, key: &str) -> Option<V>
+    where
+        V: Clone + DeserializeOwned,
+    {
+        let mark_stale = lookups.mark_stale?;
+        let cache = &lookups.memory;
+        let value = cache.get_stale(key).or_else(|| {
+            self.store
+                .as_deref()
+                .filter(|_| cache.enabled())
+                .and_then(|s| s.get_stale(cache.name, key))
+        })?;
+        cache.record_stale();
+        Some(mark_stale(value))
+    }
+
+    /// Retries `fetch` every `refresh_interval` until it succeeds, fails with
+    /// a non-retryable error, or the stale entry would no longer be kept.
+    fn spawn_refresh<V, F, Fut>(&self, lookups: &Arc<Lookups<V>>, key: String, fetch: F)
+    where
+        V: Clone + Serialize + Send + Sync + 'static,
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<V, WeatherError>> + Send + 'static,
+    {
+        if !lookups.refreshing.lock().unwrap().insert(key.clone()) {
+            return;
+        }
+        let lookups = lookups.clone();
+        let store = self.store.clone();
+        let interval = self.refresh_interval;
+        let give_up_at = Instant::now() + self.max_stale;
+
+        tokio::spawn(async move {
+            let cache = &lookups.memory;
+            loop {
+                tokio::time::sleep(interval).await;
+                match fetch().await {
+                    Ok(value) => {
+                        remember(store.as_deref(), cache, &key, &value);
+                        tracing::info!(
+                            cache = cache.name,
+                            "provider recovered, refreshed stale entry"
+                        );
+                        break;
+                    }
+                    Err(e) if e.is_retryable() && Instant::now() < give_up_at => {
+                        tracing::debug!(
+                            cache = cache.name,
+                            error = %e,
+                            "background refresh failed, retrying"
+                        );
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            cache = cache.name,
+                            error = %e,
+                            "giving up background refresh"
+                        );
+                        break;
+                    }
+                }
+            }
+            lookups.refreshing.lock().unwrap().remove(&key);
+        });
+    }
+}
+
+/// Writes a fresh upstream result to the persistent store and memory.
+fn remember<V>(store: Option<&DiskCache>, cache: &TtlCache<String, V>, key: &str, value: &V)
+where
+    V: Clone + Serialize,
+{
+    if let Some(store) = store {
+        store.put(cache.name, key, value, cache.ttl);
+    }
+    cache.insert_for(key.to_string(), value.clone(), cache.ttl);
+}
+
+/// Place names differ only in case and surrounding whitespace as far as the
+/// geocoders are concerned.
+fn query_key(query: &str) -> String {
+    query.trim().to_lowercase()
+}
+
+fn place_key(query: &PlaceQuery, limit: u32) -> String {
+    let qualifier = |q: &Option<String>| q.as_deref().map(query_key).unwrap_or_default();
+    format!(
+        "{}|{}|{}/{}",
+        query_key(&query.name),
+        qualifier(&query.state),
+        qualifier(&query.country),
+        limit
+    )
+}
+
+/// Coordinates rounded to roughly 10 m, so the same place geocoded twice
+/// shares an entry.
+fn location_key(location: &Location) -> String {
+    location.point()
+}
+
+#[async_trait]
+impl WeatherProvider for CachingProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        let inner = self.inner.clone();
+        let query = query.to_string();
+        self.cached(&self.geocode, query_key(&query), move || {
+            let (inner, query) = (inner.clone(), query.clone());
+            async move { inner.geocode(&query).await }
+        })
+        .await
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        let inner = self.inner.clone();
+        let query = query.clone();
+        self.cached(&self.candidates, place_key(&query, limit), move || {
+            let (inner, query) = (inner.clone(), query.clone());
+            async move { inner.geocode_candidates(&query, limit).await }
+        })
+        .await
+    }
+
+    fn max_candidates(&self) -> u32 {
+        self.inner.max_candidates()
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        let inner = self.inner.clone();
+        let (code, country) = (code.to_string(), country.to_string());
+        let key = query_key(&format!("{},{}", code, country));
+        self.cached(&self.postal_codes, key, move || {
+            let (inner, code, country) = (inner.clone(), code.clone(), country.clone());
+            async move { inner.geocode_postal_code(&code, &country).await }
+        })
+        .await
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        let inner = self.inner.clone();
+        let key = Location::at(lat, lon).point();
+        self.cached(&self.reverse, key, move || {
+            let inner = inner.clone();
+            async move { inner.reverse_geocode(lat, lon).await }
+        })
+        .await
+        // Nearby coordinates share an entry; answer with the ones asked about.
+        .map(|location| Location {
+            lat,
+            lon,
+            ..location
+        })
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        // Observations carry the caller's place name, so two names for the
+        // same point must not share an entry.
+        self.cached(&self.current, location.id(), move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.current(&location).await }
+        })
+        .await
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = format!("{}/{}h", location_key(&location), hours);
+        self.cached(&self.forecast, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.forecast(&location, hours).await }
+        })
+        .await
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        self.inner.air_quality_requests(hours)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = format!("{}/{}h", location_key(&location), hours);
+        self.cached(&self.air_quality, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.air_quality(&location, hours).await }
+        })
+        .await
+    }
+
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = location_key(&location);
+        self.cached(&self.alerts, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.alerts(&location).await }
+        })
+        .await
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = format!("{}/{}..{}", location_key(&location), start, end);
+        self.cached(&self.history, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.history(&location, start, end).await }
+        })
+        .await
+    }
+
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = location_key(&location);
+        self.cached(&self.nowcasts, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.nowcast(&location).await }
+        })
+        .await
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        let inner = self.inner.clone();
+        let location = location.clone();
+        let key = format!("{}/{}d", location_key(&location), days);
+        self.cached(&self.uv_pollen, key, move || {
+            let (inner, location) = (inner.clone(), location.clone());
+            async move { inner.uv_pollen(&location, days).await }
+        })
+        .await
+    }
+}
+
+/// A bounded map whose entries expire `ttl` after insertion. Expired entries
+/// are kept for a further `retention` for [`TtlCache::get_stale`]. When full,
+/// entries past retention are dropped first, then the least recently used one.
+struct TtlCache<K, V> {
+    name: &'static str,
+    ttl: Duration,
+    retention: Duration,
+    capacity: usize,
+    state: Mutex<CacheState<K, V>>,
+}
+
+struct CacheState<K, V> {
+    entries: HashMap<K, CacheEntry<V>>,
+    // Monotonic counter standing in for "last used" time in LRU ordering.
+    tick: u64,
+    stats: CacheStats,
+}
+
+struct CacheEntry<V> {
+    value: V,
+    expires_at: Instant,
+    last_used: u64,
+}
+
+impl<K: Hash + Eq + Clone, V: Clone> TtlCache<K, V> {
+    fn new(name: &'static str, ttl: Duration, retention: Duration, capacity: usize) -> Self {
+        TtlCache {
+            name,
+            ttl,
+            retention,
+            capacity,
+            state: Mutex::new(CacheState {
+                entries: HashMap::new(),
+                tick: 0,
+                stats: CacheStats::default(),
+            }),
+        }
+    }
+
+    fn enabled(&self) -> bool {
+        !self.ttl.is_zero() && self.capacity > 0
+    }
+
+    fn get(&self, key: &K) -> Option<V> {
+        if !self.enabled() {
+            return None;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let tick = state.tick;
+        let now = Instant::now();
+
+        let value = match state.entries.get_mut(key) {
+            Some(entry) if entry.expires_at > now => {
+                entry.last_used = tick;
+                Some(entry.value.clone())
+            }
+            Some(entry) if entry.expires_at + self.retention <= now => {
+                state.entries.remove(key);
+                None
+            }
+            _ => None,
+        };
+
+        if value.is_some() {
+            state.stats.hits += 1;
+        } else {
+            state.stats.misses += 1;
+        }
+        tracing::debug!(cache = self.name, hit = value.is_some(), "cache lookup");
+        value
+    }
+
+    /// Returns the entry for `key` even if expired, as long as it is still
+    /// within the retention window.
+    fn get_stale<Q>(&self, key: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let state = self.state.lock().unwrap();
+        state
+            .entries
+            .get(key)
+            .filter(|entry| entry.expires_at + self.retention > Instant::now())
+            .map(|entry| entry.value.clone())
+    }
+
+    fn record_stale(&self) {
+        self.state.lock().unwrap().stats.stale += 1;
+    }
+
+    fn record_disk_hit(&self) {
+        self.state.lock().unwrap().stats.disk_hits += 1;
+    }
+
+    fn record_coalesced(&self) {
+        self.state.lock().unwrap().stats.coalesced += 1;
+    }
+
+    /// Inserts `value` to expire after `ttl`, which may be shorter than the
+    /// cache's own when the value was already aged on disk.
+    fn insert_for(&self, key: K, value: V, ttl: Duration) {
+        if !self.enabled() {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.tick += 1;
+        let now = Instant::now();
+
+        if !state.entries.contains_key(&key) && state.entries.len() >= self.capacity {
+            let before = state.entries.len();
+            state
+                .entries
+                .retain(|_, entry| entry.expires_at + self.retention > now);
+            if state.entries.len() >= self.capacity {
+                let oldest = state
+                    .entries
+                    .iter()
+                    .min_by_key(|(_, entry)| entry.last_used)
+                    .map(|(key, _)| key.clone());
+                if let Some(oldest) = oldest {
+                    state.entries.remove(&oldest);
+                }
+            }
+            let evicted = (before - state.entries.len()) as u64;
+            state.stats.evictions += evicted;
+            tracing::debug!(cache = self.name, evicted, "cache full");
+        }
+
+        let entry = CacheEntry {
+            value,
+            expires_at: now + ttl,
+            last_used: state.tick,
+        };
+        state.entries.insert(key, entry);
+    }
+
+    fn stats(&self) -> CacheStats {
+        let state = self.state.lock().unwrap();
+        CacheStats {
+            entries: state.entries.len(),
+            ..state.stats
+        }
+    }
+}
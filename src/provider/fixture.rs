@@ -0,0 +1,99 @@
+use async_trait::async_trait;
+use serde::Deserialize;
+use std::{collections::HashMap, fs, path::Path};
+
+use super::WeatherProvider;
+use crate::error::WeatherError;
+use crate::model::{ForecastEntry, Location, Observation};
+
+/// Serves canned answers from a directory of JSON files, one per location.
+///
+/// Each `*.json` file holds a [`Fixture`]. Lookups match the query against the
+/// fixture's location name case-insensitively, ignoring surrounding whitespace.
+/// Weather for a location whose name matches no fixture is found by its
+/// [`Location::point`].
+pub struct FixtureProvider {
+    fixtures: HashMap<String, Fixture>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Fixture {
+    pub location: Location,
+    pub current: Observation,
+    #[serde(default)]
+    pub forecast: Vec<ForecastEntry>,
+}
+
+impl FixtureProvider {
+    /// Loads every fixture in `dir`, failing on the first unreadable file.
+    pub fn load(dir: &Path) -> Result<Self, String> {
+        let entries = fs::read_dir(dir)
+            .map_err(|e| format!("Failed to read fixtures from {}: {}", dir.display(), e))?;
+
+        let mut fixtures = HashMap::new();
+        for entry in entries {
+            let path = entry
+                .map_err(|e| format!("Failed to read fixtures from {}: {}", dir.display(), e))?
+                .path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let raw = fs::read_to_string(&path)
+                .map_err(|e| format!("Failed to read fixture {}: {}", path.display(), e))?;
+            let fixture: Fixture = serde_json::from_str(&raw)
+                .map_err(|e| format!("Failed to parse fixture {}: {}", path.display(), e))?;
+            fixtures.insert(normalize(&fixture.location.name), fixture);
+        }
+
+        Ok(FixtureProvider { fixtures })
+    }
+
+    fn lookup(&self, name: &str) -> Option<&Fixture> {
+        self.fixtures.get(&normalize(name))
+    }
+
+    fn lookup_location(&self, location: &Location) -> Option<&Fixture> {
+        self.lookup(&location.name).or_else(|| {
+            self.fixtures
+                .values()
+                .find(|fixture| fixture.location.point() == location.point())
+        })
+    }
+}
+
+fn normalize(name: &str) -> String {
+    name.trim().to_lowercase()
+}
+
+#[async_trait]
+impl WeatherProvider for FixtureProvider {
+    fn name(&self) -> &'static str {
+        "fixture"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.lookup(query)
+            .map(|fixture| fixture.location.clone())
+            .ok_or_else(|| WeatherError::CityNotFound(query.to_string()))
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        self.lookup_location(location)
+            .map(|fixture| fixture.current.clone())
+            .ok_or_else(|| WeatherError::CityNotFound(location.name.clone()))
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let fixture = self
+            .lookup_location(location)
+            .ok_or_else(|| WeatherError::CityNotFound(location.name.clone()))?;
+
+        // Fixtures are recorded in 3-hour steps like OpenWeatherMap's forecast.
+        let count = hours.div_ceil(3).max(1) as usize;
+        Ok(fixture.forecast.iter().take(count).cloned().collect())
+    }
+}
@@ -0,0 +1,318 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use serde::Deserialize;
+use url::Url;
+
+use super::{request, OpenMeteoProvider, UpstreamRequest, WeatherProvider};
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Condition, DailyWeather, ForecastEntry, Location, Observation, PlaceQuery,
+    Precipitation, Temperature, UvPollenDay, Wind,
+};
+
+const BASE_URL: &str = "https://api.met.no";
+
+/// The Norwegian Meteorological Institute's Locationforecast API
+/// (https://api.met.no). No API key; geocoding, air quality, pollen and
+/// history are delegated to Open-Meteo since met.no has no geocoder or
+/// archive and only covers air quality and pollen in Norway.
+pub struct MetNoProvider {
+    geocoder: OpenMeteoProvider,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl MetNoProvider {
+    pub fn new() -> Self {
+        let client = crate::http::default_client();
+        MetNoProvider {
+            geocoder: OpenMeteoProvider::new().with_client(client.clone()),
+            base_url: request::base_url(BASE_URL),
+            client,
+        }
+    }
+
+    /// Sends requests, including geocoding, through `client`. met.no's terms
+    /// of service reject requests without an identifying User-Agent, which
+    /// [`crate::http::client`] sets.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.geocoder = self.geocoder.with_client(client.clone());
+        self.client = client;
+        self
+    }
+
+    async fn timeseries(&self, location: &Location) -> Result<Vec<TimeStep>, WeatherError> {
+        // met.no asks clients to truncate coordinates to four decimals.
+        let url = UpstreamRequest::new(&self.base_url, "/weatherapi/locationforecast/2.0/complete")
+            .param("lat", format_args!("{:.4}", location.lat))
+            .param("lon", format_args!("{:.4}", location.lon))
+            .into_url();
+
+        let data: LocationForecast = super::fetch_json(self.client.get(url), "weather").await?;
+
+        Ok(data.properties.timeseries)
+    }
+}
+
+impl Default for MetNoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for MetNoProvider {
+    fn name(&self) -> &'static str {
+        "met-no"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.geocoder.geocode(query).await
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        self.geocoder.geocode_candidates(query, limit).await
+    }
+
+    fn max_candidates(&self) -> u32 {
+        self.geocoder.max_candidates()
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        self.geocoder.geocode_postal_code(code, country).await
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        self.geocoder.air_quality_requests(hours)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        self.geocoder.air_quality(location, hours).await
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        self.geocoder.history(location, start, end).await
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        self.geocoder.uv_pollen(location, days).await
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        let timeseries = self.timeseries(location).await?;
+        let step = timeseries
+            .into_iter()
+            .next()
+            .ok_or_else(|| WeatherError::Parse {
+                what: "weather",
+                message: "empty timeseries".to_string(),
+            })?;
+
+        let details = step.data.instant.details;
+        let temperature = details.air_temperature.ok_or_else(|| WeatherError::Parse {
+            what: "weather",
+            message: "missing air_temperature".to_string(),
+        })?;
+        let next = step.data.next_1_hours.or(step.data.next_6_hours);
+        Ok(Observation {
+            location: location.name.clone(),
+            country: location.country.clone(),
+            lat: location.lat,
+            lon: location.lon,
+            observed_at: parse_time(&step.time)?,
+            temperature: Temperature {
+                current: temperature,
+                feels_like: None,
+                min: None,
+                max: None,
+            },
+            humidity: details.relative_humidity,
+            pressure: details.air_pressure_at_sea_level,
+            wind: Wind {
+                speed: details.wind_speed,
+                direction: details.wind_from_direction,
+                gust: details.wind_speed_of_gust,
+            },
+            clouds: details.cloud_area_fraction,
+            visibility: None,
+            // The timeseries only forecasts precipitation for the hours ahead,
+            // so there is nothing to report for the past hour.
+            rain: Precipitation::default(),
+            snow: Precipitation::default(),
+            conditions: next
+                .and_then(|n| n.summary)
+                .map(|s| symbol_condition(&s.symbol_code))
+                .into_iter()
+                .collect(),
+            sunrise: None,
+            sunset: None,
+            timezone_offset: None,
+            stale: false,
+            age_secs: None,
+        })
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let timeseries = self.timeseries(location).await?;
+        let Some(first) = timeseries.first() else {
+            return Ok(Vec::new());
+        };
+        let horizon = parse_time(&first.time)? + i64::from(hours) * 3600;
+
+        let mut entries = Vec::new();
+        for step in timeseries {
+            let time = parse_time(&step.time)?;
+            if time >= horizon {
+                break;
+            }
+            let details = step.data.instant.details;
+            // A step without a temperature is no use as a forecast entry.
+            let Some(temp) = details.air_temperature else {
+                continue;
+            };
+            let next = step.data.next_1_hours.or(step.data.next_6_hours);
+            entries.push(ForecastEntry {
+                time: super::format_timestamp(time),
+                temp,
+                precipitation_probability: next
+                    .as_ref()
+                    .and_then(|n| n.details.as_ref())
+                    .and_then(|d| d.probability_of_precipitation)
+                    .unwrap_or_default()
+                    / 100.0,
+                wind_speed: details.wind_speed.unwrap_or_default(),
+                wind_deg: details.wind_from_direction,
+                conditions: next
+                    .and_then(|n| n.summary)
+                    .map(|s| symbol_condition(&s.symbol_code).description)
+                    .unwrap_or_default(),
+            });
+        }
+        Ok(entries)
+    }
+}
+
+fn parse_time(time: &str) -> Result<i64, WeatherError> {
+    chrono::DateTime::parse_from_rfc3339(time)
+        .map(|dt| dt.timestamp())
+        .map_err(|e| WeatherError::Parse {
+            what: "weather",
+            message: e.to_string(),
+        })
+}
+
+/// Maps a met.no symbol code (e.g. `lightrainshowers_day`) onto the closest
+/// OpenWeatherMap condition.
+fn symbol_condition(symbol: &str) -> Condition {
+    let base = symbol.split('_').next().unwrap_or(symbol);
+    let (code, main, description) = match base {
+        "clearsky" => (800, "Clear", "clear sky"),
+        "fair" => (801, "Clouds", "fair"),
+        "partlycloudy" => (802, "Clouds", "partly cloudy"),
+        "cloudy" => (804, "Clouds", "cloudy"),
+        "fog" => (741, "Fog", "fog"),
+        "lightrain" => (500, "Rain", "light rain"),
+        "rain" => (501, "Rain", "rain"),
+        "heavyrain" => (502, "Rain", "heavy rain"),
+        "lightrainshowers" => (520, "Rain", "light rain showers"),
+        "rainshowers" => (521, "Rain", "rain showers"),
+        "heavyrainshowers" => (522, "Rain", "heavy rain showers"),
+        "lightsleet" | "lightsleetshowers" => (612, "Snow", "light sleet"),
+        "sleet" | "sleetshowers" => (611, "Snow", "sleet"),
+        "heavysleet" | "heavysleetshowers" => (613, "Snow", "heavy sleet"),
+        "lightsnow" => (600, "Snow", "light snow"),
+        "snow" => (601, "Snow", "snow"),
+        "heavysnow" => (602, "Snow", "heavy snow"),
+        "lightsnowshowers" => (620, "Snow", "light snow showers"),
+        "snowshowers" => (621, "Snow", "snow showers"),
+        "heavysnowshowers" => (622, "Snow", "heavy snow showers"),
+        other if other.contains("thunder") => (211, "Thunderstorm", "thunderstorm"),
+        _ => (0, "Unknown", "unknown"),
+    };
+    Condition {
+        code,
+        main: main.to_string(),
+        description: description.to_string(),
+        icon: Some(symbol.to_string()),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct LocationForecast {
+    properties: ForecastProperties,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastProperties {
+    timeseries: Vec<TimeStep>,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeStep {
+    time: String,
+    data: TimeStepData,
+}
+
+#[derive(Debug, Deserialize)]
+struct TimeStepData {
+    instant: Instant,
+    next_1_hours: Option<Period>,
+    next_6_hours: Option<Period>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Instant {
+    details: InstantDetails,
+}
+
+#[derive(Debug, Deserialize)]
+struct InstantDetails {
+    air_temperature: Option<f64>,
+    air_pressure_at_sea_level: Option<f64>,
+    relative_humidity: Option<f64>,
+    cloud_area_fraction: Option<f64>,
+    wind_from_direction: Option<f64>,
+    wind_speed: Option<f64>,
+    wind_speed_of_gust: Option<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Period {
+    summary: Option<PeriodSummary>,
+    details: Option<PeriodDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeriodSummary {
+    symbol_code: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct PeriodDetails {
+    probability_of_precipitation: Option<f64>,
+}
@@ -0,0 +1,281 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::{header::RETRY_AFTER, StatusCode};
+use serde::de::DeserializeOwned;
+use std::{sync::Arc, time::Instant};
+
+use crate::config::{Config, ProviderKind};
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Alert, DailyWeather, ForecastEntry, Location, Nowcast, Observation,
+    PlaceQuery, UvPollenDay,
+};
+
+mod cache;
+mod fixture;
+mod met_no;
+mod nws;
+mod open_meteo;
+mod openweathermap;
+mod quota;
+mod request;
+mod resilient;
+mod single_flight;
+mod store;
+mod synthetic;
+
+pub use cache::{CacheMetrics, CacheStats, CachingProvider};
+pub use fixture::FixtureProvider;
+pub use met_no::MetNoProvider;
+pub use nws::{NwsAlertFeed, NwsAlerts};
+pub use open_meteo::OpenMeteoProvider;
+pub use openweathermap::OpenWeatherMapProvider;
+pub use quota::{QuotaProvider, QuotaUsage};
+pub use request::UpstreamRequest;
+pub use resilient::{BreakerState, ResilientProvider};
+pub use store::DiskCache;
+pub use synthetic::SyntheticProvider;
+
+/// A source of geocoding, current conditions and forecasts.
+///
+/// Implementations translate their upstream payloads into the shared
+/// [`crate::model`] types so the handler never sees provider-specific shapes.
+#[async_trait]
+pub trait WeatherProvider: Send + Sync {
+    /// Short identifier used in logs and the server instructions.
+    fn name(&self) -> &'static str;
+
+    /// Resolves a free-text place name to the best matching location.
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError>;
+
+    /// Lists up to `limit` locations matching `query`, best match first.
+    ///
+    /// Providers that can narrow the search by state or country do so
+    /// upstream. The default returns the single [`geocode`](Self::geocode)
+    /// match, ignoring the qualifiers.
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        let _ = limit;
+        Ok(vec![self.geocode(&query.name).await?])
+    }
+
+    /// Most locations [`geocode_candidates`](Self::geocode_candidates) can
+    /// list for one query.
+    fn max_candidates(&self) -> u32 {
+        1
+    }
+
+    /// Resolves a postal code within `country` (ISO 3166-1 alpha-2).
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        let _ = (code, country);
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "postal code lookup",
+        })
+    }
+
+    /// Names the place at the given coordinates.
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        let _ = (lat, lon);
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "reverse geocoding",
+        })
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError>;
+
+    /// Returns forecast entries covering at least the next `hours` hours.
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError>;
+
+    /// Returns current air quality and hourly forecasts covering the next
+    /// `hours` hours, or none if `hours` is 0.
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        let _ = (location, hours);
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "air quality",
+        })
+    }
+
+    /// How many upstream requests [`air_quality`](Self::air_quality) makes
+    /// for `hours`, so quotas can charge for each of them.
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        let _ = hours;
+        1
+    }
+
+    /// Returns the weather alerts in force at `location`, most severe first.
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        let _ = location;
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "weather alerts",
+        })
+    }
+
+    /// Returns one aggregate per day from `start` to `end` inclusive, both in
+    /// the past.
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        let _ = (location, start, end);
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "historical weather",
+        })
+    }
+
+    /// Returns precipitation intensity for about the next hour, at the
+    /// finest resolution the provider has.
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        let _ = location;
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "precipitation nowcasts",
+        })
+    }
+
+    /// Returns the UV index and pollen peaks for each of the next `days`
+    /// days, today first.
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        let _ = (location, days);
+        Err(WeatherError::Unsupported {
+            provider: self.name(),
+            feature: "UV index and pollen forecasts",
+        })
+    }
+}
+
+/// Builds the provider selected by `config`. Network providers send every
+/// request through `client` so connections are pooled across tool calls.
+pub fn from_config(
+    config: &Config,
+    client: reqwest::Client,
+) -> Result<Arc<dyn WeatherProvider>, String> {
+    Ok(match config.provider {
+        ProviderKind::OpenWeatherMap => {
+            let mut provider =
+                OpenWeatherMapProvider::new(config.openweather_api_key.clone()).with_client(client);
+            if let Some(base_url) = &config.openweather_base_url {
+                provider = provider.with_base_url(base_url.clone());
+            }
+            Arc::new(provider)
+        }
+        ProviderKind::OpenMeteo => Arc::new(OpenMeteoProvider::new().with_client(client)),
+        ProviderKind::MetNo => Arc::new(MetNoProvider::new().with_client(client)),
+        ProviderKind::Fixture => {
+            let dir = config
+                .fixtures_dir
+                .as_deref()
+                .ok_or_else(|| "WEATHER_FIXTURES_DIR not set".to_string())?;
+            Arc::new(FixtureProvider::load(dir)?)
+        }
+        ProviderKind::Synthetic => Arc::new(SyntheticProvider::new(config.synthetic_seed)),
+    })
+}
+
+/// Formats a Unix timestamp the same way OpenWeatherMap's `dt_txt` does.
+fn format_timestamp(ts: i64) -> String {
+    chrono::DateTime::from_timestamp(ts, 0)
+        .map(|dt| dt.format("%Y-%m-%d %H:%M:%S").to_string())
+        .unwrap_or_else(|| ts.to_string())
+}
+
+/// Sends `request` and decodes a JSON body, logging the upstream call.
+///
+/// `what` names the data in error messages ("Failed to get {what} data").
+/// Query strings may carry API keys, so only the host and path are logged
+/// and reqwest errors are stripped of their URL.
+async fn fetch_json<T: DeserializeOwned>(
+    request: reqwest::RequestBuilder,
+    what: &'static str,
+) -> Result<T, WeatherError> {
+    let (client, request) = request.build_split();
+    let request = request.map_err(|e| WeatherError::Upstream {
+        what,
+        status: None,
+        message: e.without_url().to_string(),
+    })?;
+    let host = request.url().host_str().unwrap_or_default().to_string();
+    let path = request.url().path().to_string();
+
+    let started = Instant::now();
+    let response = client.execute(request).await;
+    let elapsed_ms = started.elapsed().as_millis() as u64;
+
+    let response = match response {
+        Ok(response) => response,
+        Err(e) => {
+            tracing::warn!(%host, %path, elapsed_ms, timeout = e.is_timeout(), "upstream request failed");
+            return Err(if e.is_timeout() {
+                WeatherError::UpstreamTimeout { what }
+            } else {
+                WeatherError::Upstream {
+                    what,
+                    status: None,
+                    message: e.without_url().to_string(),
+                }
+            });
+        }
+    };
+
+    let status = response.status();
+    if !status.is_success() {
+        tracing::warn!(%host, %path, status = status.as_u16(), elapsed_ms, "upstream returned an error status");
+        return Err(match status {
+            StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => WeatherError::UpstreamAuth {
+                what,
+                status: status.as_u16(),
+            },
+            StatusCode::TOO_MANY_REQUESTS => WeatherError::RateLimited {
+                what,
+                retry_after: response
+                    .headers()
+                    .get(RETRY_AFTER)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(|v| v.trim().parse().ok()),
+            },
+            _ => WeatherError::Upstream {
+                what,
+                status: Some(status.as_u16()),
+                message: format!("HTTP status {}", status),
+            },
+        });
+    }
+    tracing::debug!(%host, %path, status = status.as_u16(), elapsed_ms, "upstream request");
+
+    response.json().await.map_err(|e| {
+        tracing::warn!(%host, %path, "failed to parse upstream response");
+        if e.is_timeout() {
+            WeatherError::UpstreamTimeout { what }
+        } else {
+            WeatherError::Parse {
+                what,
+                message: e.without_url().to_string(),
+            }
+        }
+    })
+}
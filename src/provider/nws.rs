@@ -0,0 +1,289 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use reqwest::header::ACCEPT;
+use serde::Deserialize;
+use std::sync::Arc;
+use url::Url;
+
+use super::{request, UpstreamRequest, WeatherProvider};
+use crate::error::WeatherError;
+use crate::geo::Geometry;
+use crate::model::{
+    AirQualityReport, Alert, AlertMatch, DailyWeather, ForecastEntry, Location, Nowcast,
+    Observation, PlaceQuery, UvPollenDay,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.weather.gov";
+
+/// Countries and territories the National Weather Service issues alerts for.
+const COVERED_COUNTRIES: &[&str] = &["US", "PR", "GU", "VI", "AS", "MP", "UM"];
+
+/// Official alerts from the US National Weather Service
+/// (https://api.weather.gov).
+///
+/// The active-alerts feed is queried for the location's point. NWS matches
+/// alerts to points by forecast zone, which can be far larger than the area
+/// warned, so alerts that carry a polygon are kept only if it contains the
+/// point. Alerts without one are trusted to the zone match. Locations outside
+/// the United States fail with [`WeatherError::Unsupported`], as does
+/// everything but [`alerts`](WeatherProvider::alerts). Combine it with a
+/// weather provider through [`NwsAlerts`].
+pub struct NwsAlertFeed {
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl NwsAlertFeed {
+    pub fn new() -> Self {
+        NwsAlertFeed {
+            base_url: request::base_url(DEFAULT_BASE_URL),
+            client: crate::http::default_client(),
+        }
+    }
+
+    /// Sends requests through `client`, typically the server's shared one.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Points the feed at another host, e.g. a local mock in tests.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    fn outside_coverage() -> WeatherError {
+        WeatherError::Unsupported {
+            provider: "nws",
+            feature: "alerts outside the United States",
+        }
+    }
+}
+
+impl Default for NwsAlertFeed {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for NwsAlertFeed {
+    fn name(&self) -> &'static str {
+        "nws"
+    }
+
+    async fn geocode(&self, _query: &str) -> Result<Location, WeatherError> {
+        Err(WeatherError::Unsupported {
+            provider: "nws",
+            feature: "geocoding",
+        })
+    }
+
+    async fn current(&self, _location: &Location) -> Result<Observation, WeatherError> {
+        Err(WeatherError::Unsupported {
+            provider: "nws",
+            feature: "current conditions",
+        })
+    }
+
+    async fn forecast(
+        &self,
+        _location: &Location,
+        _hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        Err(WeatherError::Unsupported {
+            provider: "nws",
+            feature: "forecasts",
+        })
+    }
+
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        if let Some(country) = &location.country {
+            if !COVERED_COUNTRIES.contains(&country.to_ascii_uppercase().as_str()) {
+                return Err(Self::outside_coverage());
+            }
+        }
+
+        // NWS rejects points given to more than four decimal places.
+        let alerts_url = UpstreamRequest::new(&self.base_url, "/alerts/active")
+            .param("point", location.point())
+            .into_url();
+        let request = self
+            .client
+            .get(alerts_url)
+            .header(ACCEPT, "application/geo+json");
+
+        // Points NWS doesn't cover are rejected as bad requests.
+        let collection: AlertCollection = match super::fetch_json(request, "alerts").await {
+            Err(WeatherError::Upstream {
+                status: Some(400 | 404),
+                ..
+            }) => return Err(Self::outside_coverage()),
+            result => result?,
+        };
+
+        let mut alerts: Vec<Alert> = collection
+            .features
+            .into_iter()
+            .filter_map(|feature| feature.into_alert(location))
+            .collect();
+        alerts.sort_by_key(Alert::severity_rank);
+        Ok(alerts)
+    }
+}
+
+/// Adds alerts from another source, typically an [`NwsAlertFeed`], to a
+/// provider, whatever its source of weather.
+///
+/// The two are wrapped separately, so each can have its own retries and
+/// circuit breaker and an alerts outage never holds up the weather.
+pub struct NwsAlerts {
+    inner: Arc<dyn WeatherProvider>,
+    feed: Arc<dyn WeatherProvider>,
+}
+
+impl NwsAlerts {
+    pub fn new(inner: Arc<dyn WeatherProvider>, feed: Arc<dyn WeatherProvider>) -> Self {
+        NwsAlerts { inner, feed }
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for NwsAlerts {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.inner.geocode(query).await
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        self.inner.geocode_candidates(query, limit).await
+    }
+
+    fn max_candidates(&self) -> u32 {
+        self.inner.max_candidates()
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        self.inner.geocode_postal_code(code, country).await
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        self.inner.reverse_geocode(lat, lon).await
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        self.inner.current(location).await
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        self.inner.forecast(location, hours).await
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        self.inner.air_quality_requests(hours)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        self.inner.air_quality(location, hours).await
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        self.inner.history(location, start, end).await
+    }
+
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        self.inner.nowcast(location).await
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        self.inner.uv_pollen(location, days).await
+    }
+
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        self.feed.alerts(location).await
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertCollection {
+    #[serde(default)]
+    features: Vec<AlertFeature>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AlertFeature {
+    geometry: Option<Geometry>,
+    properties: AlertProperties,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct AlertProperties {
+    event: String,
+    severity: Option<String>,
+    headline: Option<String>,
+    area_desc: Option<String>,
+    onset: Option<String>,
+    effective: Option<String>,
+    ends: Option<String>,
+    expires: Option<String>,
+    description: Option<String>,
+    instruction: Option<String>,
+    sender_name: Option<String>,
+}
+
+impl AlertFeature {
+    /// The alert, unless its polygon leaves out `location`.
+    fn into_alert(self, location: &Location) -> Option<Alert> {
+        let matched_by = match &self.geometry {
+            // Points and lines enclose nothing, so only the zone match is left.
+            None | Some(Geometry::Other) => AlertMatch::Zone,
+            Some(area) if area.contains(location.lat, location.lon) => AlertMatch::Polygon,
+            Some(_) => return None,
+        };
+        let p = self.properties;
+        Some(Alert {
+            event: p.event,
+            severity: p.severity.unwrap_or_else(|| "Unknown".to_string()),
+            headline: p.headline,
+            area: p.area_desc,
+            // `ends` is when the hazard is over; `expires` only when the
+            // message is superseded.
+            onset: p.onset.or(p.effective),
+            expires: p.ends.or(p.expires),
+            description: p.description,
+            instruction: p.instruction,
+            sender: p.sender_name,
+            matched_by,
+        })
+    }
+}
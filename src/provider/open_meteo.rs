@@ -0,0 +1,758 @@
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate};
+use serde::Deserialize;
+use std::ops::RangeInclusive;
+use url::Url;
+
+use super::{request, UpstreamRequest, WeatherProvider};
+use crate::error::WeatherError;
+use crate::model::{
+    AirQuality, AirQualityReport, AqiScale, Condition, DailyWeather, Exposure, ForecastEntry,
+    Location, Nowcast, NowcastStep, Observation, PlaceQuery, PollenKind, Precipitation,
+    Temperature, UvPollenDay, Wind,
+};
+
+const GEOCODING_BASE_URL: &str = "https://geocoding-api.open-meteo.com";
+const FORECAST_BASE_URL: &str = "https://api.open-meteo.com";
+const AIR_QUALITY_BASE_URL: &str = "https://air-quality-api.open-meteo.com";
+const ARCHIVE_BASE_URL: &str = "https://archive-api.open-meteo.com";
+
+/// The first day the historical archive covers.
+const ARCHIVE_START: NaiveDate = NaiveDate::from_ymd_opt(1940, 1, 1).unwrap();
+/// Days the archive trails today by. More recent days are read from the
+/// forecast API, which keeps the past three months.
+const ARCHIVE_DELAY_DAYS: u64 = 7;
+const HISTORY_COVERAGE: &str = "1940-01-01 to yesterday";
+
+// The geocoding API returns at most 100 matches; nobody needs that many.
+const MAX_GEOCODE_RESULTS: u32 = 10;
+
+const CURRENT_FIELDS: &str = "temperature_2m,relative_humidity_2m,apparent_temperature,\
+precipitation,weather_code,cloud_cover,pressure_msl,wind_speed_10m,\
+wind_direction_10m,wind_gusts_10m,visibility";
+const DAILY_FIELDS: &str = "sunrise,sunset,temperature_2m_max,temperature_2m_min";
+const AIR_QUALITY_FIELDS: &str =
+    "us_aqi,pm2_5,pm10,ozone,nitrogen_dioxide,sulphur_dioxide,carbon_monoxide";
+const HOURLY_FIELDS: &str =
+    "temperature_2m,precipitation_probability,weather_code,wind_speed_10m,wind_direction_10m";
+const HISTORY_FIELDS: &str = "temperature_2m,precipitation,weather_code";
+const UV_POLLEN_FIELDS: &str = "uv_index,grass_pollen,alder_pollen,birch_pollen,olive_pollen,\
+mugwort_pollen,ragweed_pollen";
+
+/// Open-Meteo (https://open-meteo.com). Free for non-commercial use, no API key.
+pub struct OpenMeteoProvider {
+    geocoding_base: Url,
+    forecast_base: Url,
+    air_quality_base: Url,
+    archive_base: Url,
+    client: reqwest::Client,
+}
+
+impl OpenMeteoProvider {
+    pub fn new() -> Self {
+        OpenMeteoProvider {
+            geocoding_base: request::base_url(GEOCODING_BASE_URL),
+            forecast_base: request::base_url(FORECAST_BASE_URL),
+            air_quality_base: request::base_url(AIR_QUALITY_BASE_URL),
+            archive_base: request::base_url(ARCHIVE_BASE_URL),
+            client: crate::http::default_client(),
+        }
+    }
+
+    /// Points every Open-Meteo API at one host, e.g. a local mock in tests.
+    /// Their paths don't overlap.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.geocoding_base = base_url.clone();
+        self.forecast_base = base_url.clone();
+        self.air_quality_base = base_url.clone();
+        self.archive_base = base_url;
+        self
+    }
+
+    /// Sends requests through `client`, typically the server's shared one.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+}
+
+impl Default for OpenMeteoProvider {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenMeteoProvider {
+    fn name(&self) -> &'static str {
+        "open-meteo"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        let geo_url = UpstreamRequest::new(&self.geocoding_base, "/v1/search")
+            .param("name", query)
+            .param("count", 1)
+            .param("format", "json")
+            .into_url();
+
+        let geo_data: GeocodingResponse =
+            super::fetch_json(self.client.get(geo_url), "geo").await?;
+
+        geo_data
+            .results
+            .into_iter()
+            .next()
+            .map(GeocodingResult::into_location)
+            .ok_or_else(|| WeatherError::CityNotFound(query.to_string()))
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        let mut geo_url = UpstreamRequest::new(&self.geocoding_base, "/v1/search")
+            .param("name", query.name.trim())
+            .param("count", limit.clamp(1, MAX_GEOCODE_RESULTS))
+            .param("format", "json");
+        if let Some(country) = &query.country {
+            geo_url = geo_url.param("countryCode", country.trim().to_ascii_uppercase());
+        }
+
+        let geo_data: GeocodingResponse =
+            super::fetch_json(self.client.get(geo_url.into_url()), "geo").await?;
+        if geo_data.results.is_empty() {
+            return Err(WeatherError::CityNotFound(query.to_string()));
+        }
+        // The search has no state filter; the caller ranks by state instead.
+        Ok(geo_data
+            .results
+            .into_iter()
+            .map(GeocodingResult::into_location)
+            .collect())
+    }
+
+    fn max_candidates(&self) -> u32 {
+        MAX_GEOCODE_RESULTS
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        // The search matches postal codes as well as names.
+        let query = PlaceQuery {
+            country: Some(country.to_string()),
+            ..PlaceQuery::new(code)
+        };
+        let candidates = self.geocode_candidates(&query, 1).await?;
+        Ok(candidates
+            .into_iter()
+            .next()
+            .expect("at least one candidate"))
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        let weather_url = UpstreamRequest::new(&self.forecast_base, "/v1/forecast")
+            .param("latitude", location.lat)
+            .param("longitude", location.lon)
+            .param("current", CURRENT_FIELDS)
+            .param("daily", DAILY_FIELDS)
+            .param("forecast_days", 1)
+            .param("timezone", "auto")
+            .param("timeformat", "unixtime")
+            .param("wind_speed_unit", "ms")
+            .into_url();
+
+        let weather_data: CurrentResponse =
+            super::fetch_json(self.client.get(weather_url), "weather").await?;
+
+        let current = weather_data.current;
+        let daily = weather_data.daily.unwrap_or_default();
+        Ok(Observation {
+            location: location.name.clone(),
+            country: location.country.clone(),
+            lat: location.lat,
+            lon: location.lon,
+            observed_at: current.time,
+            temperature: Temperature {
+                current: current.temperature_2m,
+                feels_like: current.apparent_temperature,
+                min: daily.temperature_2m_min.first().copied(),
+                max: daily.temperature_2m_max.first().copied(),
+            },
+            humidity: current.relative_humidity_2m,
+            pressure: current.pressure_msl,
+            wind: Wind {
+                speed: current.wind_speed_10m,
+                direction: current.wind_direction_10m,
+                gust: current.wind_gusts_10m,
+            },
+            clouds: current.cloud_cover,
+            visibility: current.visibility,
+            // The current block covers the last 15 minutes, not the last hour.
+            rain: Precipitation::default(),
+            snow: Precipitation::default(),
+            conditions: current
+                .weather_code
+                .map(wmo_condition)
+                .into_iter()
+                .collect(),
+            sunrise: daily.sunrise.first().copied(),
+            sunset: daily.sunset.first().copied(),
+            timezone_offset: weather_data.utc_offset_seconds,
+            stale: false,
+            age_secs: None,
+        })
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let forecast_url = UpstreamRequest::new(&self.forecast_base, "/v1/forecast")
+            .param("latitude", location.lat)
+            .param("longitude", location.lon)
+            .param("hourly", HOURLY_FIELDS)
+            .param("forecast_hours", hours.max(1))
+            .param("timeformat", "unixtime")
+            .param("wind_speed_unit", "ms")
+            .into_url();
+
+        let forecast_data: ForecastResponse =
+            super::fetch_json(self.client.get(forecast_url), "forecast").await?;
+
+        let hourly = forecast_data.hourly;
+        Ok(hourly
+            .time
+            .iter()
+            .enumerate()
+            .map(|(i, &time)| ForecastEntry {
+                time: super::format_timestamp(time),
+                temp: hourly
+                    .temperature_2m
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default(),
+                precipitation_probability: hourly
+                    .precipitation_probability
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default()
+                    / 100.0,
+                wind_speed: hourly
+                    .wind_speed_10m
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .unwrap_or_default(),
+                wind_deg: hourly.wind_direction_10m.get(i).copied().flatten(),
+                conditions: hourly
+                    .weather_code
+                    .get(i)
+                    .copied()
+                    .flatten()
+                    .map(|code| wmo_condition(code).description)
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        let mut url = UpstreamRequest::new(&self.air_quality_base, "/v1/air-quality")
+            .param("latitude", location.lat)
+            .param("longitude", location.lon)
+            .param("current", AIR_QUALITY_FIELDS)
+            .param("timeformat", "unixtime");
+        if hours > 0 {
+            url = url
+                .param("hourly", AIR_QUALITY_FIELDS)
+                .param("forecast_hours", hours);
+        }
+
+        let data: AirQualityResponse =
+            super::fetch_json(self.client.get(url.into_url()), "air quality").await?;
+
+        let current = data
+            .current
+            .into_air_quality()
+            .ok_or_else(|| WeatherError::Parse {
+                what: "air quality",
+                message: "no AQI for this location".to_string(),
+            })?;
+        let hourly = data.hourly.unwrap_or_default();
+        // Hours the model has no AQI for are left out.
+        let forecast = hourly
+            .time
+            .iter()
+            .enumerate()
+            .filter_map(|(i, &time)| {
+                let at = |values: &Vec<Option<f64>>| values.get(i).copied().flatten();
+                AirQualityValues {
+                    time,
+                    us_aqi: at(&hourly.us_aqi),
+                    pm2_5: at(&hourly.pm2_5),
+                    pm10: at(&hourly.pm10),
+                    ozone: at(&hourly.ozone),
+                    nitrogen_dioxide: at(&hourly.nitrogen_dioxide),
+                    sulphur_dioxide: at(&hourly.sulphur_dioxide),
+                    carbon_monoxide: at(&hourly.carbon_monoxide),
+                }
+                .into_air_quality()
+            })
+            .collect();
+
+        Ok(AirQualityReport { current, forecast })
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        let today = chrono::Utc::now().date_naive();
+        let unavailable = |date: NaiveDate| WeatherError::DateUnavailable {
+            provider: "open-meteo",
+            date: date.to_string(),
+            coverage: HISTORY_COVERAGE,
+        };
+        if start < ARCHIVE_START {
+            return Err(unavailable(start));
+        }
+        if end >= today {
+            return Err(unavailable(end));
+        }
+
+        let recent = today
+            .checked_sub_days(Days::new(ARCHIVE_DELAY_DAYS))
+            .is_none_or(|archived| end > archived);
+        let url = if recent {
+            UpstreamRequest::new(&self.forecast_base, "/v1/forecast")
+        } else {
+            UpstreamRequest::new(&self.archive_base, "/v1/archive")
+        }
+        .param("latitude", location.lat)
+        .param("longitude", location.lon)
+        .param("start_date", start)
+        .param("end_date", end)
+        .param("hourly", HISTORY_FIELDS)
+        .param("timezone", "auto")
+        .into_url();
+
+        let data: HistoryResponse =
+            super::fetch_json(self.client.get(url), "historical weather").await?;
+
+        let mut days = data.hourly.into_days();
+        days.retain(|day| {
+            day.date
+                .parse()
+                .is_ok_and(|date: NaiveDate| (start..=end).contains(&date))
+        });
+        if days.is_empty() {
+            return Err(unavailable(start));
+        }
+        Ok(days)
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        let url = UpstreamRequest::new(&self.air_quality_base, "/v1/air-quality")
+            .param("latitude", location.lat)
+            .param("longitude", location.lon)
+            .param("hourly", UV_POLLEN_FIELDS)
+            .param("forecast_days", days.max(1))
+            .param("timezone", "auto")
+            .into_url();
+
+        let data: UvPollenResponse =
+            super::fetch_json(self.client.get(url), "UV and pollen").await?;
+
+        Ok(data.hourly.into_days())
+    }
+
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        // Five steps, so the hour is still covered once the current one ends.
+        let url = UpstreamRequest::new(&self.forecast_base, "/v1/forecast")
+            .param("latitude", location.lat)
+            .param("longitude", location.lon)
+            .param("minutely_15", "precipitation")
+            .param("forecast_minutely_15", 5)
+            .param("timeformat", "unixtime")
+            .into_url();
+
+        let data: NowcastResponse = super::fetch_json(self.client.get(url), "nowcast").await?;
+
+        // Each value is the total over the 15 minutes before its time.
+        let minutely = data.minutely_15;
+        Ok(Nowcast {
+            resolution_minutes: 15,
+            steps: minutely
+                .time
+                .iter()
+                .zip(&minutely.precipitation)
+                .map(|(&time, mm)| NowcastStep {
+                    time: time - 15 * 60,
+                    intensity: mm.unwrap_or_default() * 4.0,
+                })
+                .collect(),
+        })
+    }
+}
+
+/// Maps a WMO weather interpretation code onto the closest OpenWeatherMap condition.
+pub(super) fn wmo_condition(code: u32) -> Condition {
+    let (owm, main, description) = match code {
+        0 => (800, "Clear", "clear sky"),
+        1 => (801, "Clouds", "mainly clear"),
+        2 => (802, "Clouds", "partly cloudy"),
+        3 => (804, "Clouds", "overcast"),
+        45 | 48 => (741, "Fog", "fog"),
+        51 => (300, "Drizzle", "light drizzle"),
+        53 => (301, "Drizzle", "drizzle"),
+        55 => (302, "Drizzle", "dense drizzle"),
+        56 | 57 => (511, "Rain", "freezing drizzle"),
+        61 => (500, "Rain", "light rain"),
+        63 => (501, "Rain", "moderate rain"),
+        65 => (502, "Rain", "heavy rain"),
+        66 | 67 => (511, "Rain", "freezing rain"),
+        71 => (600, "Snow", "light snow"),
+        73 => (601, "Snow", "snow"),
+        75 => (602, "Snow", "heavy snow"),
+        77 => (600, "Snow", "snow grains"),
+        80 => (520, "Rain", "light rain showers"),
+        81 => (521, "Rain", "rain showers"),
+        82 => (522, "Rain", "violent rain showers"),
+        85 => (620, "Snow", "light snow showers"),
+        86 => (622, "Snow", "heavy snow showers"),
+        95 => (211, "Thunderstorm", "thunderstorm"),
+        96 | 99 => (202, "Thunderstorm", "thunderstorm with hail"),
+        _ => (0, "Unknown", "unknown"),
+    };
+    Condition {
+        code: owm,
+        main: main.to_string(),
+        description: description.to_string(),
+        icon: None,
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResponse {
+    #[serde(default)]
+    results: Vec<GeocodingResult>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeocodingResult {
+    name: String,
+    latitude: f64,
+    longitude: f64,
+    country_code: Option<String>,
+    admin1: Option<String>,
+}
+
+impl GeocodingResult {
+    fn into_location(self) -> Location {
+        Location {
+            name: self.name,
+            country: self.country_code,
+            state: self.admin1,
+            lat: self.latitude,
+            lon: self.longitude,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentResponse {
+    utc_offset_seconds: Option<i32>,
+    current: CurrentValues,
+    daily: Option<DailyValues>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CurrentValues {
+    time: i64,
+    temperature_2m: f64,
+    relative_humidity_2m: Option<f64>,
+    apparent_temperature: Option<f64>,
+    weather_code: Option<u32>,
+    cloud_cover: Option<f64>,
+    pressure_msl: Option<f64>,
+    wind_speed_10m: Option<f64>,
+    wind_direction_10m: Option<f64>,
+    wind_gusts_10m: Option<f64>,
+    visibility: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct DailyValues {
+    #[serde(default)]
+    sunrise: Vec<i64>,
+    #[serde(default)]
+    sunset: Vec<i64>,
+    #[serde(default)]
+    temperature_2m_max: Vec<f64>,
+    #[serde(default)]
+    temperature_2m_min: Vec<f64>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ForecastResponse {
+    hourly: HourlyValues,
+}
+
+#[derive(Debug, Deserialize)]
+struct HourlyValues {
+    time: Vec<i64>,
+    #[serde(default)]
+    temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation_probability: Vec<Option<f64>>,
+    #[serde(default)]
+    weather_code: Vec<Option<u32>>,
+    #[serde(default)]
+    wind_speed_10m: Vec<Option<f64>>,
+    #[serde(default)]
+    wind_direction_10m: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct HistoryResponse {
+    hourly: HistoryHourly,
+}
+
+/// Hourly history with local ISO 8601 times, e.g. "2024-01-01T13:00".
+#[derive(Debug, Deserialize)]
+struct HistoryHourly {
+    time: Vec<String>,
+    #[serde(default)]
+    temperature_2m: Vec<Option<f64>>,
+    #[serde(default)]
+    precipitation: Vec<Option<f64>>,
+    #[serde(default)]
+    weather_code: Vec<Option<u32>>,
+}
+
+/// Splits local ISO 8601 hours, e.g. "2024-01-01T13:00", into their dates
+/// and the index range of each date's hours.
+fn local_days(times: &[String]) -> Vec<(&str, RangeInclusive<usize>)> {
+    let mut days = Vec::new();
+    let mut hours = times.iter().enumerate().peekable();
+    while let Some(&(first, time)) = hours.peek() {
+        let date = time.split('T').next().unwrap_or(time);
+        let mut last = first;
+        while let Some((i, _)) = hours.next_if(|(_, t)| t.starts_with(date)) {
+            last = i;
+        }
+        days.push((date, first..=last));
+    }
+    days
+}
+
+impl HistoryHourly {
+    /// Aggregates the hours by local date. Days without any temperatures,
+    /// which the archive has until it catches up, are left out.
+    fn into_days(self) -> Vec<DailyWeather> {
+        let mut days = Vec::new();
+        for (date, range) in local_days(&self.time) {
+            let slice = |values: &[Option<f64>]| -> Vec<f64> {
+                values
+                    .get(range.clone())
+                    .unwrap_or_default()
+                    .iter()
+                    .flatten()
+                    .copied()
+                    .collect()
+            };
+
+            let temps = slice(&self.temperature_2m);
+            if temps.is_empty() {
+                continue;
+            }
+            // Most hours wins; ties go to the higher, more severe, code.
+            let mut counts: Vec<(u32, usize)> = Vec::new();
+            for &code in self
+                .weather_code
+                .get(range.clone())
+                .unwrap_or_default()
+                .iter()
+                .flatten()
+            {
+                match counts.iter_mut().find(|(c, _)| *c == code) {
+                    Some((_, n)) => *n += 1,
+                    None => counts.push((code, 1)),
+                }
+            }
+            let dominant = counts.into_iter().max_by_key(|&(code, n)| (n, code));
+
+            days.push(DailyWeather {
+                date: date.to_string(),
+                temp_min: temps.iter().copied().fold(f64::INFINITY, f64::min),
+                temp_max: temps.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                temp_mean: temps.iter().sum::<f64>() / temps.len() as f64,
+                precipitation: slice(&self.precipitation).iter().sum(),
+                conditions: dominant
+                    .map(|(code, _)| wmo_condition(code).description)
+                    .unwrap_or_else(|| "unknown".to_string()),
+            });
+        }
+        days
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct UvPollenResponse {
+    hourly: UvPollenHourly,
+}
+
+/// Hourly UV index and pollen in grains/m³, with local ISO 8601 times.
+#[derive(Debug, Deserialize)]
+struct UvPollenHourly {
+    time: Vec<String>,
+    #[serde(default)]
+    uv_index: Vec<Option<f64>>,
+    #[serde(default)]
+    grass_pollen: Vec<Option<f64>>,
+    #[serde(default)]
+    alder_pollen: Vec<Option<f64>>,
+    #[serde(default)]
+    birch_pollen: Vec<Option<f64>>,
+    #[serde(default)]
+    olive_pollen: Vec<Option<f64>>,
+    #[serde(default)]
+    mugwort_pollen: Vec<Option<f64>>,
+    #[serde(default)]
+    ragweed_pollen: Vec<Option<f64>>,
+}
+
+impl UvPollenHourly {
+    fn into_days(self) -> Vec<UvPollenDay> {
+        let tree = sum_hourly(&[&self.alder_pollen, &self.birch_pollen, &self.olive_pollen]);
+        let weed = sum_hourly(&[&self.mugwort_pollen, &self.ragweed_pollen]);
+        local_days(&self.time)
+            .into_iter()
+            .map(|(date, range)| {
+                // The first hour with the day's highest reading, if any.
+                let peak = |values: &[Option<f64>]| {
+                    let mut peak: Option<(f64, usize)> = None;
+                    for i in range.clone() {
+                        if let Some(value) = values.get(i).copied().flatten() {
+                            if peak.is_none_or(|(max, _)| value > max) {
+                                peak = Some((value, i));
+                            }
+                        }
+                    }
+                    peak.map(|(value, i)| {
+                        let time = self.time[i].split('T').nth(1).unwrap_or_default();
+                        (value, time.to_string())
+                    })
+                };
+                let pollen = |kind, values: &[Option<f64>]| {
+                    peak(values).map(|(value, time)| Exposure::pollen(kind, value, time))
+                };
+                UvPollenDay {
+                    date: date.to_string(),
+                    uv: peak(&self.uv_index).map(|(value, time)| Exposure::uv(value, time)),
+                    grass: pollen(PollenKind::Grass, &self.grass_pollen),
+                    tree: pollen(PollenKind::Tree, &tree),
+                    weed: pollen(PollenKind::Weed, &weed),
+                }
+            })
+            .collect()
+    }
+}
+
+/// Adds up hourly series, hour by hour. An hour is `None` only if every
+/// series lacks it.
+fn sum_hourly(series: &[&Vec<Option<f64>>]) -> Vec<Option<f64>> {
+    let hours = series.iter().map(|s| s.len()).max().unwrap_or_default();
+    (0..hours)
+        .map(|i| {
+            series
+                .iter()
+                .filter_map(|s| s.get(i).copied().flatten())
+                .reduce(|a, b| a + b)
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastResponse {
+    minutely_15: NowcastValues,
+}
+
+#[derive(Debug, Deserialize)]
+struct NowcastValues {
+    time: Vec<i64>,
+    #[serde(default)]
+    precipitation: Vec<Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirQualityResponse {
+    current: AirQualityValues,
+    hourly: Option<AirQualityHourly>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AirQualityValues {
+    time: i64,
+    us_aqi: Option<f64>,
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    ozone: Option<f64>,
+    nitrogen_dioxide: Option<f64>,
+    sulphur_dioxide: Option<f64>,
+    carbon_monoxide: Option<f64>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct AirQualityHourly {
+    #[serde(default)]
+    time: Vec<i64>,
+    #[serde(default)]
+    us_aqi: Vec<Option<f64>>,
+    #[serde(default)]
+    pm2_5: Vec<Option<f64>>,
+    #[serde(default)]
+    pm10: Vec<Option<f64>>,
+    #[serde(default)]
+    ozone: Vec<Option<f64>>,
+    #[serde(default)]
+    nitrogen_dioxide: Vec<Option<f64>>,
+    #[serde(default)]
+    sulphur_dioxide: Vec<Option<f64>>,
+    #[serde(default)]
+    carbon_monoxide: Vec<Option<f64>>,
+}
+
+impl AirQualityValues {
+    fn into_air_quality(self) -> Option<AirQuality> {
+        let aqi = self.us_aqi?.round().max(0.0) as u32;
+        Some(AirQuality {
+            time: super::format_timestamp(self.time),
+            aqi,
+            scale: AqiScale::UsEpa,
+            category: AqiScale::UsEpa.category(aqi).to_string(),
+            pm2_5: self.pm2_5,
+            pm10: self.pm10,
+            o3: self.ozone,
+            no2: self.nitrogen_dioxide,
+            so2: self.sulphur_dioxide,
+            co: self.carbon_monoxide,
+        })
+    }
+}
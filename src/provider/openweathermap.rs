@@ -0,0 +1,533 @@
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use url::Url;
+
+use super::{request, UpstreamRequest, WeatherProvider};
+use crate::error::WeatherError;
+use crate::model::{
+    AirQuality, AirQualityReport, AqiScale, Condition, ForecastEntry, Location, Nowcast,
+    NowcastStep, Observation, PlaceQuery, Precipitation, Temperature, Wind,
+};
+
+pub const DEFAULT_BASE_URL: &str = "https://api.openweathermap.org";
+
+// The geocoding API returns at most this many matches.
+const MAX_GEOCODE_RESULTS: u32 = 5;
+
+// The free forecast covers 5 days in 3-hour steps.
+const FORECAST_STEP_HOURS: u32 = 3;
+
+pub struct OpenWeatherMapProvider {
+    api_key: Option<String>,
+    base_url: Url,
+    client: reqwest::Client,
+}
+
+impl OpenWeatherMapProvider {
+    /// Creates the provider, registering `api_key` for redaction.
+    pub fn new(api_key: Option<String>) -> Self {
+        if let Some(key) = &api_key {
+            crate::redact::register_secret(key);
+        }
+        OpenWeatherMapProvider {
+            api_key,
+            base_url: request::base_url(DEFAULT_BASE_URL),
+            client: crate::http::default_client(),
+        }
+    }
+
+    /// Sends requests through `client`, typically the server's shared one.
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Points the provider at another host, e.g. a local mock in tests.
+    pub fn with_base_url(mut self, base_url: Url) -> Self {
+        self.base_url = base_url;
+        self
+    }
+
+    fn api_key(&self) -> Result<&str, WeatherError> {
+        self.api_key
+            .as_deref()
+            .ok_or(WeatherError::MissingConfig("OPENWEATHER_API_KEY"))
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for OpenWeatherMapProvider {
+    fn name(&self) -> &'static str {
+        "openweathermap"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        let api_key = self.api_key()?;
+        let geo_url = UpstreamRequest::new(&self.base_url, "/geo/1.0/direct")
+            .param("q", query)
+            .param("limit", 1)
+            .param("appid", api_key)
+            .into_url();
+
+        let geo_data: Vec<GeoResponse> = super::fetch_json(self.client.get(geo_url), "geo").await?;
+
+        geo_data
+            .into_iter()
+            .next()
+            .map(|geo| geo.into_location(query))
+            .ok_or_else(|| WeatherError::CityNotFound(query.to_string()))
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        let api_key = self.api_key()?;
+        // The API takes qualifiers as "city,state,country"; it only honours
+        // the state for US locations.
+        let q = [
+            Some(&query.name),
+            query.state.as_ref(),
+            query.country.as_ref(),
+        ]
+        .into_iter()
+        .flatten()
+        .map(|part| part.trim())
+        .collect::<Vec<_>>()
+        .join(",");
+        let geo_url = UpstreamRequest::new(&self.base_url, "/geo/1.0/direct")
+            .param("q", &q)
+            .param("limit", limit.clamp(1, MAX_GEOCODE_RESULTS))
+            .param("appid", api_key)
+            .into_url();
+
+        let geo_data: Vec<GeoResponse> = super::fetch_json(self.client.get(geo_url), "geo").await?;
+        if geo_data.is_empty() {
+            return Err(WeatherError::CityNotFound(query.to_string()));
+        }
+        Ok(geo_data
+            .into_iter()
+            .map(|geo| geo.into_location(&query.name))
+            .collect())
+    }
+
+    fn max_candidates(&self) -> u32 {
+        MAX_GEOCODE_RESULTS
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        let api_key = self.api_key()?;
+        let zip = format!("{},{}", code.trim(), country.trim());
+        let geo_url = UpstreamRequest::new(&self.base_url, "/geo/1.0/zip")
+            .param("zip", &zip)
+            .param("appid", api_key)
+            .into_url();
+
+        // Unknown codes come back as a 404 rather than an empty result.
+        match super::fetch_json::<GeoResponse>(self.client.get(geo_url), "geo").await {
+            Ok(geo) => Ok(geo.into_location(code.trim())),
+            Err(WeatherError::Upstream {
+                status: Some(404), ..
+            }) => Err(WeatherError::CityNotFound(zip)),
+            Err(e) => Err(e),
+        }
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        let api_key = self.api_key()?;
+        let geo_url = UpstreamRequest::new(&self.base_url, "/geo/1.0/reverse")
+            .param("lat", lat)
+            .param("lon", lon)
+            .param("limit", 1)
+            .param("appid", api_key)
+            .into_url();
+
+        let geo_data: Vec<GeoResponse> = super::fetch_json(self.client.get(geo_url), "geo").await?;
+
+        // The API answers with the nearest named place, but keep the
+        // coordinates that were asked about.
+        let fallback = Location::at(lat, lon);
+        geo_data
+            .into_iter()
+            .next()
+            .map(|geo| Location {
+                lat,
+                lon,
+                ..geo.into_location(&fallback.name)
+            })
+            .ok_or(WeatherError::CityNotFound(fallback.name))
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        let api_key = self.api_key()?;
+        let weather_url = UpstreamRequest::new(&self.base_url, "/data/2.5/weather")
+            .param("lat", location.lat)
+            .param("lon", location.lon)
+            .param("appid", api_key)
+            .param("units", "metric")
+            .into_url();
+
+        let weather_data: WeatherResponse =
+            super::fetch_json(self.client.get(weather_url), "weather").await?;
+
+        Ok(weather_data.into_observation(location))
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let api_key = self.api_key()?;
+
+        // The endpoint returns one entry per 3-hour step; round the horizon up.
+        let count = hours.div_ceil(FORECAST_STEP_HOURS).max(1);
+        let forecast_url = UpstreamRequest::new(&self.base_url, "/data/2.5/forecast")
+            .param("lat", location.lat)
+            .param("lon", location.lon)
+            .param("cnt", count)
+            .param("appid", api_key)
+            .param("units", "metric")
+            .into_url();
+
+        let forecast_data: ForecastResponse =
+            super::fetch_json(self.client.get(forecast_url), "forecast").await?;
+
+        Ok(forecast_data
+            .list
+            .into_iter()
+            .map(|item| ForecastEntry {
+                time: item
+                    .dt_txt
+                    .unwrap_or_else(|| super::format_timestamp(item.dt)),
+                temp: item.main.temp,
+                precipitation_probability: item.pop,
+                wind_speed: item.wind.speed.unwrap_or_default(),
+                wind_deg: item.wind.deg,
+                conditions: item
+                    .weather
+                    .first()
+                    .map(|c| c.description.clone())
+                    .unwrap_or_default(),
+            })
+            .collect())
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        // The forecast comes from a second endpoint.
+        1 + u32::from(hours > 0)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        let api_key = self.api_key()?;
+        let url = |path| {
+            UpstreamRequest::new(&self.base_url, path)
+                .param("lat", location.lat)
+                .param("lon", location.lon)
+                .param("appid", api_key)
+                .into_url()
+        };
+
+        let current: AirPollutionResponse = super::fetch_json(
+            self.client.get(url("/data/2.5/air_pollution")),
+            "air quality",
+        )
+        .await?;
+        let current = current
+            .list
+            .into_iter()
+            .next()
+            .ok_or_else(|| WeatherError::Parse {
+                what: "air quality",
+                message: "no readings for this location".to_string(),
+            })?;
+
+        // The forecast is a separate call, so it is only made when asked for.
+        let mut forecast = Vec::new();
+        if hours > 0 {
+            let response: AirPollutionResponse = super::fetch_json(
+                self.client.get(url("/data/2.5/air_pollution/forecast")),
+                "air quality",
+            )
+            .await?;
+            forecast = response
+                .list
+                .into_iter()
+                .filter(|item| item.dt > current.dt)
+                .take(hours as usize)
+                .map(AirPollutionItem::into_air_quality)
+                .collect();
+        }
+
+        Ok(AirQualityReport {
+            current: current.into_air_quality(),
+            forecast,
+        })
+    }
+
+    /// Reads the minutely block of One Call 3.0, which needs its own
+    /// subscription; keys without one are rejected as unauthorized.
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        let api_key = self.api_key()?;
+        let url = UpstreamRequest::new(&self.base_url, "/data/3.0/onecall")
+            .param("lat", location.lat)
+            .param("lon", location.lon)
+            .param("exclude", "current,hourly,daily,alerts")
+            .param("appid", api_key)
+            .into_url();
+
+        let data: OneCallResponse = super::fetch_json(self.client.get(url), "nowcast").await?;
+
+        // Minutely data is only published where radar coverage allows.
+        let minutely = data.minutely.ok_or(WeatherError::Unsupported {
+            provider: "openweathermap",
+            feature: "precipitation nowcasts at this location",
+        })?;
+        Ok(Nowcast {
+            resolution_minutes: 1,
+            steps: minutely
+                .into_iter()
+                .map(|minute| NowcastStep {
+                    time: minute.dt,
+                    intensity: minute.precipitation,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeoResponse {
+    lat: f64,
+    lon: f64,
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+impl GeoResponse {
+    fn into_location(self, fallback_name: &str) -> Location {
+        let field = |key: &str| {
+            self.extra
+                .get(key)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+        };
+        Location {
+            name: field("name").unwrap_or_else(|| fallback_name.to_string()),
+            country: field("country"),
+            state: field("state"),
+            lat: self.lat,
+            lon: self.lon,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherResponse {
+    #[serde(default)]
+    weather: Vec<WeatherCondition>,
+    main: WeatherMain,
+    visibility: Option<f64>,
+    #[serde(default)]
+    wind: WeatherWind,
+    clouds: Option<WeatherClouds>,
+    #[serde(default)]
+    rain: WeatherVolume,
+    #[serde(default)]
+    snow: WeatherVolume,
+    dt: i64,
+    sys: Option<WeatherSys>,
+    timezone: Option<i32>,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherMain {
+    temp: f64,
+    feels_like: Option<f64>,
+    temp_min: Option<f64>,
+    temp_max: Option<f64>,
+    pressure: Option<f64>,
+    humidity: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherCondition {
+    id: u32,
+    main: String,
+    description: String,
+    icon: Option<String>,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WeatherWind {
+    speed: Option<f64>,
+    deg: Option<f64>,
+    gust: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherClouds {
+    all: f64,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct WeatherVolume {
+    #[serde(rename = "1h")]
+    last_1h: Option<f64>,
+    #[serde(rename = "3h")]
+    last_3h: Option<f64>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct WeatherSys {
+    country: Option<String>,
+    sunrise: Option<i64>,
+    sunset: Option<i64>,
+}
+
+impl WeatherResponse {
+    fn into_observation(self, location: &Location) -> Observation {
+        let sys = self.sys;
+        Observation {
+            location: if self.name.is_empty() {
+                location.name.clone()
+            } else {
+                self.name
+            },
+            country: sys
+                .as_ref()
+                .and_then(|s| s.country.clone())
+                .or_else(|| location.country.clone()),
+            lat: location.lat,
+            lon: location.lon,
+            observed_at: self.dt,
+            temperature: Temperature {
+                current: self.main.temp,
+                feels_like: self.main.feels_like,
+                min: self.main.temp_min,
+                max: self.main.temp_max,
+            },
+            humidity: self.main.humidity,
+            pressure: self.main.pressure,
+            wind: Wind {
+                speed: self.wind.speed,
+                direction: self.wind.deg,
+                gust: self.wind.gust,
+            },
+            clouds: self.clouds.map(|c| c.all),
+            visibility: self.visibility,
+            rain: Precipitation {
+                last_1h: self.rain.last_1h,
+                last_3h: self.rain.last_3h,
+            },
+            snow: Precipitation {
+                last_1h: self.snow.last_1h,
+                last_3h: self.snow.last_3h,
+            },
+            conditions: self
+                .weather
+                .into_iter()
+                .map(|c| Condition {
+                    code: c.id,
+                    main: c.main,
+                    description: c.description,
+                    icon: c.icon,
+                })
+                .collect(),
+            sunrise: sys.as_ref().and_then(|s| s.sunrise),
+            sunset: sys.as_ref().and_then(|s| s.sunset),
+            timezone_offset: self.timezone,
+            stale: false,
+            age_secs: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ForecastResponse {
+    list: Vec<ForecastItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct ForecastItem {
+    dt: i64,
+    main: WeatherMain,
+    #[serde(default)]
+    weather: Vec<WeatherCondition>,
+    #[serde(default)]
+    wind: WeatherWind,
+    #[serde(default)]
+    pop: f64,
+    dt_txt: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OneCallResponse {
+    minutely: Option<Vec<OneCallMinute>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OneCallMinute {
+    dt: i64,
+    /// Rate in mm/h.
+    precipitation: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AirPollutionResponse {
+    #[serde(default)]
+    list: Vec<AirPollutionItem>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AirPollutionItem {
+    dt: i64,
+    main: AirPollutionIndex,
+    #[serde(default)]
+    components: AirPollutionComponents,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct AirPollutionIndex {
+    aqi: u32,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct AirPollutionComponents {
+    pm2_5: Option<f64>,
+    pm10: Option<f64>,
+    o3: Option<f64>,
+    no2: Option<f64>,
+    so2: Option<f64>,
+    co: Option<f64>,
+}
+
+impl AirPollutionItem {
+    fn into_air_quality(self) -> AirQuality {
+        let c = self.components;
+        AirQuality {
+            time: super::format_timestamp(self.dt),
+            aqi: self.main.aqi,
+            scale: AqiScale::Openweathermap,
+            category: AqiScale::Openweathermap.category(self.main.aqi).to_string(),
+            pm2_5: c.pm2_5,
+            pm10: c.pm10,
+            o3: c.o3,
+            no2: c.no2,
+            so2: c.so2,
+            co: c.co,
+        }
+    }
+}
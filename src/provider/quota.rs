@@ -0,0 +1,272 @@
+use async_trait::async_trait;
+use chrono::{Days, NaiveDate, Utc};
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+use super::{DiskCache, WeatherProvider};
+use crate::config::QuotaConfig;
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Alert, DailyWeather, ForecastEntry, Location, Nowcast, Observation,
+    PlaceQuery, UvPollenDay,
+};
+
+/// Wraps another provider with client-side limits matching the API plan.
+///
+/// Every upstream request spends a token from a bucket holding `per_minute`
+/// tokens and refilled continuously over a minute, and counts towards
+/// `per_day` calls per UTC day. Calls that would exceed either limit fail with
+/// [`WeatherError::QuotaExceeded`] without reaching the provider, and calls
+/// failing with [`WeatherError::Unsupported`] cost nothing. The daily
+/// count is kept in the [`DiskCache`] when one is attached, so restarts don't
+/// reset it.
+pub struct QuotaProvider {
+    inner: Arc<dyn WeatherProvider>,
+    limits: QuotaConfig,
+    store: Option<Arc<DiskCache>>,
+    state: Mutex<QuotaState>,
+}
+
+struct QuotaState {
+    tokens: f64,
+    refilled_at: Instant,
+    day: NaiveDate,
+    calls_today: u32,
+}
+
+/// Calls made today against the configured daily limit.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QuotaUsage {
+    pub calls_today: u32,
+    /// `None` when there is no daily limit.
+    pub daily_limit: Option<u32>,
+}
+
+impl QuotaProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>, limits: &QuotaConfig) -> Self {
+        QuotaProvider {
+            inner,
+            limits: limits.clone(),
+            store: None,
+            state: Mutex::new(QuotaState {
+                tokens: f64::from(limits.per_minute),
+                refilled_at: Instant::now(),
+                day: today(),
+                calls_today: 0,
+            }),
+        }
+    }
+
+    /// Persists the daily count to `store`, resuming today's count from it.
+    pub fn with_store(mut self, store: impl Into<Arc<DiskCache>>) -> Self {
+        let store = store.into();
+        {
+            let mut state = self.state.lock().unwrap();
+            state.calls_today = store.daily_calls(&state.day.to_string());
+        }
+        self.store = Some(store);
+        self
+    }
+
+    pub fn usage(&self) -> QuotaUsage {
+        let mut state = self.state.lock().unwrap();
+        state.roll_over(today());
+        QuotaUsage {
+            calls_today: state.calls_today,
+            daily_limit: (self.limits.per_day > 0).then_some(self.limits.per_day),
+        }
+    }
+
+    /// Spends `requests` calls from both limits, or explains which one ran
+    /// out.
+    fn acquire(&self, requests: u32) -> Result<NaiveDate, WeatherError> {
+        let mut state = self.state.lock().unwrap();
+        let now = Instant::now();
+        let per_minute = f64::from(self.limits.per_minute);
+
+        state.roll_over(today());
+        if self.limits.per_day > 0 && state.calls_today + requests > self.limits.per_day {
+            return Err(self.exceeded("day", self.limits.per_day, secs_until_midnight()));
+        }
+
+        if self.limits.per_minute > 0 {
+            let refill = now.duration_since(state.refilled_at).as_secs_f64() * per_minute / 60.0;
+            state.tokens = (state.tokens + refill).min(per_minute);
+            state.refilled_at = now;
+            // A call needing more than a full bucket waits for a full one.
+            let needed = f64::from(requests).min(per_minute);
+            if state.tokens < needed {
+                let wait = (needed - state.tokens) * 60.0 / per_minute;
+                return Err(self.exceeded("minute", self.limits.per_minute, wait.ceil() as u64));
+            }
+            state.tokens -= needed;
+        }
+
+        state.calls_today += requests;
+        if let Some(store) = &self.store {
+            store.set_daily_calls(&state.day.to_string(), state.calls_today);
+        }
+        Ok(state.day)
+    }
+
+    /// Gives back what [`acquire`](Self::acquire) spent on `day`.
+    fn refund(&self, requests: u32, day: NaiveDate) {
+        let mut state = self.state.lock().unwrap();
+        if self.limits.per_minute > 0 {
+            let per_minute = f64::from(self.limits.per_minute);
+            state.tokens = (state.tokens + f64::from(requests).min(per_minute)).min(per_minute);
+        }
+        if state.day == day {
+            state.calls_today = state.calls_today.saturating_sub(requests);
+            if let Some(store) = &self.store {
+                store.set_daily_calls(&state.day.to_string(), state.calls_today);
+            }
+        }
+    }
+
+    /// Runs `call` against both limits, refunding it if it failed before
+    /// reaching the provider.
+    async fn metered<T>(
+        &self,
+        requests: u32,
+        call: impl Future<Output = Result<T, WeatherError>>,
+    ) -> Result<T, WeatherError> {
+        let day = self.acquire(requests)?;
+        let result = call.await;
+        if result.as_ref().is_err_and(|e| !e.reached_upstream()) {
+            self.refund(requests, day);
+        }
+        result
+    }
+
+    fn exceeded(&self, window: &'static str, limit: u32, retry_after: u64) -> WeatherError {
+        tracing::warn!(
+            provider = self.inner.name(),
+            window,
+            limit,
+            retry_after,
+            "client-side quota reached"
+        );
+        WeatherError::QuotaExceeded {
+            provider: self.inner.name(),
+            window,
+            limit,
+            retry_after,
+        }
+    }
+}
+
+impl QuotaState {
+    fn roll_over(&mut self, today: NaiveDate) {
+        if today != self.day {
+            self.day = today;
+            self.calls_today = 0;
+        }
+    }
+}
+
+fn today() -> NaiveDate {
+    Utc::now().date_naive()
+}
+
+fn secs_until_midnight() -> u64 {
+    let now = Utc::now();
+    let midnight = now
+        .date_naive()
+        .checked_add_days(Days::new(1))
+        .and_then(|day| day.and_hms_opt(0, 0, 0))
+        .map(|midnight| midnight.and_utc());
+    midnight.map_or(0, |midnight| (midnight - now).num_seconds().max(1) as u64)
+}
+
+#[async_trait]
+impl WeatherProvider for QuotaProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.metered(1, self.inner.geocode(query)).await
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        self.metered(1, self.inner.geocode_candidates(query, limit))
+            .await
+    }
+
+    fn max_candidates(&self) -> u32 {
+        self.inner.max_candidates()
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        self.metered(1, self.inner.geocode_postal_code(code, country))
+            .await
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        self.metered(1, self.inner.reverse_geocode(lat, lon)).await
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        self.metered(1, self.inner.current(location)).await
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        self.metered(1, self.inner.forecast(location, hours)).await
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        self.inner.air_quality_requests(hours)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        self.metered(
+            self.inner.air_quality_requests(hours),
+            self.inner.air_quality(location, hours),
+        )
+        .await
+    }
+
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        self.metered(1, self.inner.alerts(location)).await
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        self.metered(1, self.inner.history(location, start, end))
+            .await
+    }
+
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        self.metered(1, self.inner.nowcast(location)).await
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        self.metered(1, self.inner.uv_pollen(location, days)).await
+    }
+}
@@ -0,0 +1,46 @@
+use std::fmt::Display;
+use url::Url;
+
+/// An upstream URL built from a fixed path and individually encoded query
+/// parameters.
+///
+/// Parameter names are `&'static str` so only code can choose them; values
+/// are always percent-encoded, so no value can add, remove or override
+/// another parameter or change the path.
+#[derive(Debug, Clone)]
+pub struct UpstreamRequest {
+    url: Url,
+}
+
+impl UpstreamRequest {
+    /// Starts a request for `path` below `base`, keeping any path prefix
+    /// `base` already has (e.g. when running behind a proxy).
+    pub fn new(base: &Url, path: &'static str) -> Self {
+        let mut url = base.clone();
+        let prefix = url.path().trim_end_matches('/').to_string();
+        url.set_path(&format!("{}{}", prefix, path));
+        url.set_query(None);
+        url.set_fragment(None);
+        UpstreamRequest { url }
+    }
+
+    pub fn param(mut self, key: &'static str, value: impl Display) -> Self {
+        self.url
+            .query_pairs_mut()
+            .append_pair(key, &value.to_string());
+        self
+    }
+
+    pub fn url(&self) -> &Url {
+        &self.url
+    }
+
+    pub fn into_url(self) -> Url {
+        self.url
+    }
+}
+
+/// Parses a compile-time base URL.
+pub(super) fn base_url(url: &'static str) -> Url {
+    Url::parse(url).expect("provider base URLs are valid")
+}
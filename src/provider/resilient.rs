@@ -0,0 +1,288 @@
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use std::{
+    future::Future,
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::time::Instant;
+
+use super::WeatherProvider;
+use crate::config::RetryConfig;
+use crate::error::WeatherError;
+use crate::model::{
+    AirQualityReport, Alert, DailyWeather, ForecastEntry, Location, Nowcast, Observation,
+    PlaceQuery, UvPollenDay,
+};
+
+/// Wraps another provider with retries and a circuit breaker.
+///
+/// Retryable failures are retried up to `max_attempts` times with jittered
+/// exponential backoff; a 429's `Retry-After`, or the wait for client-side
+/// quota, is waited out instead, unless it exceeds `max_delay`. Timeouts,
+/// connection failures and 5xx responses count towards the breaker; once
+/// `breaker_threshold` calls in a row have failed it opens and calls fail fast
+/// with [`WeatherError::CircuitOpen`] for `breaker_cooldown`. After that a
+/// single trial call is let through, which closes the breaker on success or
+/// reopens it on failure.
+pub struct ResilientProvider {
+    inner: Arc<dyn WeatherProvider>,
+    policy: RetryConfig,
+    breaker: Mutex<Breaker>,
+}
+
+/// Where the circuit breaker is in its cycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BreakerState {
+    /// Calls go through; consecutive failures are being counted.
+    Closed,
+    /// Calls fail fast until the cooldown passes.
+    Open,
+    /// One trial call is in flight to probe whether the provider recovered.
+    HalfOpen,
+}
+
+struct Breaker {
+    state: BreakerState,
+    failures: u32,
+    /// When the breaker last opened, or the trial call started if half-open.
+    since: Instant,
+}
+
+impl ResilientProvider {
+    pub fn new(inner: Arc<dyn WeatherProvider>, policy: &RetryConfig) -> Self {
+        ResilientProvider {
+            inner,
+            policy: policy.clone(),
+            breaker: Mutex::new(Breaker {
+                state: BreakerState::Closed,
+                failures: 0,
+                since: Instant::now(),
+            }),
+        }
+    }
+
+    pub fn breaker_state(&self) -> BreakerState {
+        self.breaker.lock().unwrap().state
+    }
+
+    async fn call<T, F, Fut>(&self, operation: &'static str, call: F) -> Result<T, WeatherError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, WeatherError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            self.admit()?;
+            let result = call().await;
+            self.record(&result);
+
+            let error = match result {
+                Err(e) if e.is_retryable() && attempt < self.policy.max_attempts => e,
+                result => return result,
+            };
+            let Some(delay) = self.backoff(attempt, &error) else {
+                return Err(error);
+            };
+            tracing::debug!(
+                provider = self.inner.name(),
+                operation,
+                attempt,
+                delay_ms = delay.as_millis() as u64,
+                error = %error,
+                "retrying upstream call"
+            );
+            tokio::time::sleep(delay).await;
+            attempt += 1;
+        }
+    }
+
+    /// How long to wait before retry number `attempt`, or `None` if the
+    /// upstream asked for a longer wait than the policy allows.
+    fn backoff(&self, attempt: u32, error: &WeatherError) -> Option<Duration> {
+        if let WeatherError::RateLimited {
+            retry_after: Some(secs),
+            ..
+        }
+        | WeatherError::QuotaExceeded {
+            retry_after: secs, ..
+        } = error
+        {
+            let delay = Duration::from_secs(*secs);
+            return (delay <= self.policy.max_delay).then_some(delay);
+        }
+
+        let exponential = self
+            .policy
+            .base_delay
+            .saturating_mul(1 << (attempt - 1).min(16))
+            .min(self.policy.max_delay);
+        // "Equal jitter": half the delay is fixed, half random, so concurrent
+        // callers spread out without any of them retrying immediately.
+        let half = exponential.as_millis() as u64 / 2;
+        Some(Duration::from_millis(half + fastrand::u64(0..=half)))
+    }
+
+    /// Fails fast while the breaker is open, and lets one trial call through
+    /// once the cooldown has passed.
+    fn admit(&self) -> Result<(), WeatherError> {
+        let mut breaker = self.breaker.lock().unwrap();
+        let cooldown = self.policy.breaker_cooldown;
+        let ready_at = breaker.since + cooldown;
+        let now = Instant::now();
+
+        match breaker.state {
+            BreakerState::Closed => Ok(()),
+            // A trial that has not reported back within a cooldown was most
+            // likely cancelled, so another caller may take its place.
+            BreakerState::Open | BreakerState::HalfOpen if now >= ready_at => {
+                if breaker.state == BreakerState::Open {
+                    tracing::info!(provider = self.inner.name(), "circuit breaker half-open");
+                }
+                breaker.state = BreakerState::HalfOpen;
+                breaker.since = now;
+                Ok(())
+            }
+            BreakerState::Open | BreakerState::HalfOpen => Err(WeatherError::CircuitOpen {
+                provider: self.inner.name(),
+                retry_after: (ready_at - now).as_secs_f64().ceil() as u64,
+            }),
+        }
+    }
+
+    fn record<T>(&self, result: &Result<T, WeatherError>) {
+        // Only errors suggesting the provider is unreachable or broken count.
+        // A 429 or a 404 shows it is up and answering. A call that never
+        // reached the provider says nothing about its health.
+        let failed = match result {
+            Ok(_) => false,
+            Err(e) if !e.reached_upstream() => return,
+            Err(e @ (WeatherError::UpstreamTimeout { .. } | WeatherError::Upstream { .. })) => {
+                e.is_retryable()
+            }
+            Err(_) => false,
+        };
+        let mut breaker = self.breaker.lock().unwrap();
+        let provider = self.inner.name();
+
+        if !failed {
+            if breaker.state != BreakerState::Closed {
+                tracing::info!(provider, "circuit breaker closed");
+            }
+            breaker.state = BreakerState::Closed;
+            breaker.failures = 0;
+            return;
+        }
+
+        breaker.failures = breaker.failures.saturating_add(1);
+        let threshold = self.policy.breaker_threshold;
+        let cooldown_secs = self.policy.breaker_cooldown.as_secs();
+        match breaker.state {
+            BreakerState::HalfOpen => {
+                tracing::warn!(provider, cooldown_secs, "circuit breaker reopened");
+            }
+            BreakerState::Closed if threshold > 0 && breaker.failures >= threshold => {
+                tracing::warn!(
+                    provider,
+                    failures = breaker.failures,
+                    cooldown_secs,
+                    "circuit breaker opened"
+                );
+            }
+            _ => return,
+        }
+        breaker.state = BreakerState::Open;
+        breaker.since = Instant::now();
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for ResilientProvider {
+    fn name(&self) -> &'static str {
+        self.inner.name()
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.call("geocode", || self.inner.geocode(query)).await
+    }
+
+    async fn geocode_candidates(
+        &self,
+        query: &PlaceQuery,
+        limit: u32,
+    ) -> Result<Vec<Location>, WeatherError> {
+        self.call("geocode", || self.inner.geocode_candidates(query, limit))
+            .await
+    }
+
+    fn max_candidates(&self) -> u32 {
+        self.inner.max_candidates()
+    }
+
+    async fn geocode_postal_code(
+        &self,
+        code: &str,
+        country: &str,
+    ) -> Result<Location, WeatherError> {
+        self.call("geocode", || self.inner.geocode_postal_code(code, country))
+            .await
+    }
+
+    async fn reverse_geocode(&self, lat: f64, lon: f64) -> Result<Location, WeatherError> {
+        self.call("geocode", || self.inner.reverse_geocode(lat, lon))
+            .await
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        self.call("current", || self.inner.current(location)).await
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        self.call("forecast", || self.inner.forecast(location, hours))
+            .await
+    }
+
+    fn air_quality_requests(&self, hours: u32) -> u32 {
+        self.inner.air_quality_requests(hours)
+    }
+
+    async fn air_quality(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<AirQualityReport, WeatherError> {
+        self.call("air_quality", || self.inner.air_quality(location, hours))
+            .await
+    }
+
+    async fn alerts(&self, location: &Location) -> Result<Vec<Alert>, WeatherError> {
+        self.call("alerts", || self.inner.alerts(location)).await
+    }
+
+    async fn history(
+        &self,
+        location: &Location,
+        start: NaiveDate,
+        end: NaiveDate,
+    ) -> Result<Vec<DailyWeather>, WeatherError> {
+        self.call("history", || self.inner.history(location, start, end))
+            .await
+    }
+
+    async fn nowcast(&self, location: &Location) -> Result<Nowcast, WeatherError> {
+        self.call("nowcast", || self.inner.nowcast(location)).await
+    }
+
+    async fn uv_pollen(
+        &self,
+        location: &Location,
+        days: u32,
+    ) -> Result<Vec<UvPollenDay>, WeatherError> {
+        self.call("uv_pollen", || self.inner.uv_pollen(location, days))
+            .await
+    }
+}
@@ -0,0 +1,59 @@
+use std::{
+    collections::HashMap,
+    future::Future,
+    sync::{Arc, Mutex},
+};
+use tokio::sync::OnceCell;
+
+use crate::error::WeatherError;
+
+type Flight<V> = Arc<OnceCell<Result<V, WeatherError>>>;
+
+/// Deduplicates concurrent lookups for the same key.
+///
+/// The first caller for a key runs its fetch; callers arriving while it is in
+/// flight wait for it and receive a clone of the same result or error. The
+/// flight is forgotten once it completes, so errors are not remembered. If the
+/// running caller is cancelled, one of the waiters runs its own fetch instead.
+pub(super) struct SingleFlight<V> {
+    flights: Mutex<HashMap<String, Flight<V>>>,
+}
+
+impl<V: Clone> SingleFlight<V> {
+    pub(super) fn new() -> Self {
+        SingleFlight {
+            flights: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Runs `fetch` unless a lookup for `key` is already in flight. Also
+    /// returns whether this call joined another caller's flight.
+    pub(super) async fn run(
+        &self,
+        key: &str,
+        fetch: impl Future<Output = Result<V, WeatherError>>,
+    ) -> (Result<V, WeatherError>, bool) {
+        let (flight, joined) = {
+            let mut flights = self.flights.lock().unwrap();
+            match flights.get(key) {
+                Some(flight) => (flight.clone(), true),
+                None => {
+                    let flight = Flight::default();
+                    flights.insert(key.to_string(), flight.clone());
+                    (flight, false)
+                }
+            }
+        };
+
+        let result = flight.get_or_init(|| fetch).await.clone();
+
+        let mut flights = self.flights.lock().unwrap();
+        if flights
+            .get(key)
+            .is_some_and(|current| Arc::ptr_eq(current, &flight))
+        {
+            flights.remove(key);
+        }
+        (result, joined)
+    }
+}
@@ -0,0 +1,227 @@
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{de::DeserializeOwned, Serialize};
+use std::{
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Schema migrations, applied in order. `PRAGMA user_version` records how many
+/// have run, so append new steps here and never edit existing ones.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE entries (
+        kind TEXT NOT NULL,
+        key TEXT NOT NULL,
+        value TEXT NOT NULL,
+        stored_at INTEGER NOT NULL,
+        expires_at INTEGER NOT NULL,
+        PRIMARY KEY (kind, key)
+    );
+    CREATE INDEX entries_by_age ON entries (kind, stored_at);
+    CREATE INDEX entries_by_expiry ON entries (expires_at);",
+    "CREATE TABLE quota_usage (
+        day TEXT PRIMARY KEY,
+        calls INTEGER NOT NULL
+    );",
+];
+
+/// A SQLite file holding cached lookups and daily quota usage across
+/// restarts.
+///
+/// Values are stored as JSON under a `(kind, key)` pair with an absolute
+/// expiry time. Expired rows are kept for a further `retention` to answer for
+/// an unreachable provider, then purged when the store is opened or the kind
+/// is written to. Each kind is capped at `max_entries` rows, oldest first.
+/// Lookups are local and quick, so they run inline rather than on a blocking
+/// thread. Read and write failures are logged and treated as misses; the store
+/// never fails a tool call.
+pub struct DiskCache {
+    conn: Mutex<Connection>,
+    max_entries: usize,
+    retention: i64,
+}
+
+impl DiskCache {
+    /// Opens or creates the store at `path`, migrating it to the current
+    /// schema.
+    pub fn open(path: &Path, max_entries: usize, retention: Duration) -> Result<Self, String> {
+        let retention = millis(retention);
+        let fail = |e: rusqlite::Error| format!("Failed to open cache {}: {}", path.display(), e);
+
+        let mut conn = Connection::open(path).map_err(fail)?;
+        let version: usize = conn
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(fail)?;
+        if version > MIGRATIONS.len() {
+            return Err(format!(
+                "Cache {} has schema version {}, but this server only supports up to {}",
+                path.display(),
+                version,
+                MIGRATIONS.len()
+            ));
+        }
+
+        let tx = conn.transaction().map_err(fail)?;
+        for migration in &MIGRATIONS[version..] {
+            tx.execute_batch(migration).map_err(fail)?;
+        }
+        tx.pragma_update(None, "user_version", MIGRATIONS.len())
+            .map_err(fail)?;
+        let purged = tx
+            .execute(
+                "DELETE FROM entries WHERE expires_at <= ?1",
+                params![now_millis().saturating_sub(retention)],
+            )
+            .map_err(fail)?;
+        tx.commit().map_err(fail)?;
+
+        tracing::debug!(path = %path.display(), purged, "opened persistent cache");
+        Ok(DiskCache {
+            conn: Mutex::new(conn),
+            max_entries,
+            retention,
+        })
+    }
+
+    /// Returns the value stored under `(kind, key)` and how long it remains
+    /// valid, unless it is missing or expired.
+    pub(super) fn get<V: DeserializeOwned>(&self, kind: &str, key: &str) -> Option<(V, Duration)> {
+        let now = now_millis();
+        self.lookup(kind, key, now)
+            .map(|(value, expires_at)| (value, Duration::from_millis((expires_at - now) as u64)))
+    }
+
+    /// Returns the value stored under `(kind, key)` even if it has expired,
+    /// as long as it is still within the retention window.
+    pub(super) fn get_stale<V: DeserializeOwned>(&self, kind: &str, key: &str) -> Option<V> {
+        self.lookup(kind, key, now_millis().saturating_sub(self.retention))
+            .map(|(value, _)| value)
+    }
+
+    /// Reads the entry under `(kind, key)` that expires after `after`, with its
+    /// expiry time.
+    fn lookup<V: DeserializeOwned>(&self, kind: &str, key: &str, after: i64) -> Option<(V, i64)> {
+        let row: Option<(String, i64)> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value, expires_at FROM entries
+                 WHERE kind = ?1 AND key = ?2 AND expires_at > ?3",
+                params![kind, key, after],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()
+            .inspect_err(|e| tracing::warn!(kind, error = %e, "persistent cache read failed"))
+            .ok()
+            .flatten();
+
+        let (raw, expires_at) = row?;
+        match serde_json::from_str(&raw) {
+            Ok(value) => Some((value, expires_at)),
+            Err(e) => {
+                // Written by an older build with a different model shape.
+                tracing::debug!(kind, error = %e, "discarding unreadable cache entry");
+                None
+            }
+        }
+    }
+
+    /// Stores `value` under `(kind, key)` for `ttl`, then evicts expired rows
+    /// and any beyond the size bound.
+    pub(super) fn put<V: Serialize>(&self, kind: &str, key: &str, value: &V, ttl: Duration) {
+        let raw = match serde_json::to_string(value) {
+            Ok(raw) => raw,
+            Err(e) => {
+                tracing::warn!(kind, error = %e, "failed to serialize cache entry");
+                return;
+            }
+        };
+        let now = now_millis();
+        let expires_at = now.saturating_add(millis(ttl));
+
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .execute(
+                "INSERT OR REPLACE INTO entries (kind, key, value, stored_at, expires_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![kind, key, raw, now, expires_at],
+            )
+            .and_then(|_| {
+                conn.execute(
+                    "DELETE FROM entries WHERE kind = ?1 AND expires_at <= ?2",
+                    params![kind, now.saturating_sub(self.retention)],
+                )
+            })
+            .and_then(|expired| {
+                let overflow = conn.execute(
+                    "DELETE FROM entries WHERE kind = ?1 AND rowid IN (
+                         SELECT rowid FROM entries WHERE kind = ?1
+                         ORDER BY stored_at DESC, rowid DESC LIMIT -1 OFFSET ?2
+                     )",
+                    params![kind, self.max_entries],
+                )?;
+                Ok(expired + overflow)
+            });
+
+        match result {
+            Ok(0) => {}
+            Ok(evicted) => tracing::debug!(kind, evicted, "evicted persistent cache entries"),
+            Err(e) => tracing::warn!(kind, error = %e, "persistent cache write failed"),
+        }
+    }
+
+    /// Upstream calls recorded for `day` (`YYYY-MM-DD`, UTC).
+    pub(super) fn daily_calls(&self, day: &str) -> u32 {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT calls FROM quota_usage WHERE day = ?1",
+                params![day],
+                |row| row.get(0),
+            )
+            .optional()
+            .inspect_err(|e| tracing::warn!(error = %e, "failed to read quota usage"))
+            .ok()
+            .flatten()
+            .unwrap_or(0)
+    }
+
+    /// Stores the call count for `day`, forgetting earlier days.
+    pub(super) fn set_daily_calls(&self, day: &str, calls: u32) {
+        let conn = self.conn.lock().unwrap();
+        let result = conn
+            .execute(
+                "INSERT OR REPLACE INTO quota_usage (day, calls) VALUES (?1, ?2)",
+                params![day, calls],
+            )
+            .and_then(|_| conn.execute("DELETE FROM quota_usage WHERE day < ?1", params![day]));
+        if let Err(e) = result {
+            tracing::warn!(error = %e, "failed to record quota usage");
+        }
+    }
+
+    /// Number of rows currently stored for `kind`, expired or not.
+    pub fn len(&self, kind: &str) -> usize {
+        self.conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT COUNT(*) FROM entries WHERE kind = ?1",
+                params![kind],
+                |row| row.get(0),
+            )
+            .unwrap_or(0)
+    }
+}
+
+fn millis(duration: Duration) -> i64 {
+    duration.as_millis().try_into().unwrap_or(i64::MAX)
+}
+
+fn now_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
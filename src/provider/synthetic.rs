@@ -0,0 +1,187 @@
+use async_trait::async_trait;
+
+use super::WeatherProvider;
+use crate::error::WeatherError;
+use crate::model::{
+    Condition, ForecastEntry, Location, Observation, Precipitation, Temperature, Wind,
+};
+
+// All synthetic observations are anchored at 2024-01-01T12:00:00Z so that
+// output is identical between runs, not just within one.
+const REFERENCE_TIME: i64 = 1_704_110_400;
+const STEP_SECONDS: i64 = 3 * 3600;
+
+const CONDITIONS: [(u32, &str, &str); 6] = [
+    (800, "Clear", "clear sky"),
+    (801, "Clouds", "few clouds"),
+    (803, "Clouds", "broken clouds"),
+    (500, "Rain", "light rain"),
+    (501, "Rain", "moderate rain"),
+    (600, "Snow", "light snow"),
+];
+
+/// Generates plausible, fully deterministic weather for any place name.
+///
+/// Every value is derived from the seed and the normalized query, so the same
+/// seed always produces the same answers.
+pub struct SyntheticProvider {
+    seed: u64,
+}
+
+impl SyntheticProvider {
+    pub fn new(seed: u64) -> Self {
+        SyntheticProvider { seed }
+    }
+
+    fn rng(&self, name: &str, salt: u64) -> SplitMix64 {
+        SplitMix64(self.seed ^ fnv1a(&name.trim().to_lowercase()) ^ salt)
+    }
+
+    fn step(&self, location: &Location, index: u64) -> (f64, f64, f64, f64, usize) {
+        let mut rng = self.rng(&location.name, index.wrapping_mul(0x9e37_79b9));
+        // Colder towards the poles, with some per-step variation.
+        let base = 30.0 - location.lat.abs() * 0.5;
+        let temp = round1(base + rng.range(-6.0, 6.0));
+        let pop = round1(rng.range(0.0, 1.0));
+        let wind_speed = round1(rng.range(0.0, 12.0));
+        let wind_deg = rng.range(0.0, 360.0).round();
+        let condition = if pop > 0.6 {
+            if temp < 0.0 {
+                5
+            } else {
+                3 + (rng.next() % 2) as usize
+            }
+        } else {
+            (rng.next() % 3) as usize
+        };
+        (temp, pop, wind_speed, wind_deg, condition)
+    }
+}
+
+fn condition(index: usize) -> Condition {
+    let (code, main, description) = CONDITIONS[index];
+    Condition {
+        code,
+        main: main.to_string(),
+        description: description.to_string(),
+        icon: None,
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for SyntheticProvider {
+    fn name(&self) -> &'static str {
+        "synthetic"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        let name = query.trim();
+        if name.is_empty() {
+            return Err(WeatherError::CityNotFound(query.to_string()));
+        }
+        let mut rng = self.rng(name, 0);
+        Ok(Location {
+            name: name.to_string(),
+            country: Some("ZZ".to_string()),
+            state: None,
+            lat: round4(rng.range(-60.0, 70.0)),
+            lon: round4(rng.range(-180.0, 180.0)),
+        })
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        let (temp, pop, wind_speed, wind_deg, condition_index) = self.step(location, 0);
+        let mut rng = self.rng(&location.name, 1);
+        let raining = (3..=4).contains(&condition_index);
+        let snowing = condition_index == 5;
+        Ok(Observation {
+            location: location.name.clone(),
+            country: location.country.clone(),
+            lat: location.lat,
+            lon: location.lon,
+            observed_at: REFERENCE_TIME,
+            temperature: Temperature {
+                current: temp,
+                feels_like: Some(round1(temp - wind_speed * 0.3)),
+                min: Some(round1(temp - 3.0)),
+                max: Some(round1(temp + 3.0)),
+            },
+            humidity: Some((40.0 + pop * 55.0).round()),
+            pressure: Some(rng.range(995.0, 1030.0).round()),
+            wind: Wind {
+                speed: Some(wind_speed),
+                direction: Some(wind_deg),
+                gust: Some(round1(wind_speed * 1.4)),
+            },
+            clouds: Some((pop * 100.0).round()),
+            visibility: Some(10_000.0),
+            rain: Precipitation {
+                last_1h: raining.then(|| round1(pop * 3.0)),
+                last_3h: None,
+            },
+            snow: Precipitation {
+                last_1h: snowing.then(|| round1(pop * 2.0)),
+                last_3h: None,
+            },
+            conditions: vec![condition(condition_index)],
+            sunrise: Some(REFERENCE_TIME - 5 * 3600),
+            sunset: Some(REFERENCE_TIME + 5 * 3600),
+            timezone_offset: Some(((location.lon / 15.0).round() as i32) * 3600),
+            stale: false,
+            age_secs: None,
+        })
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        let count = u64::from(hours.div_ceil(3).max(1));
+        Ok((1..=count)
+            .map(|i| {
+                let (temp, pop, wind_speed, wind_deg, condition_index) = self.step(location, i);
+                ForecastEntry {
+                    time: super::format_timestamp(REFERENCE_TIME + i as i64 * STEP_SECONDS),
+                    temp,
+                    precipitation_probability: pop,
+                    wind_speed,
+                    wind_deg: Some(wind_deg),
+                    conditions: condition(condition_index).description,
+                }
+            })
+            .collect())
+    }
+}
+
+/// Stable across platforms and Rust versions, unlike `DefaultHasher`.
+fn fnv1a(s: &str) -> u64 {
+    s.bytes().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+struct SplitMix64(u64);
+
+impl SplitMix64 {
+    fn next(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    fn range(&mut self, min: f64, max: f64) -> f64 {
+        let unit = (self.next() >> 11) as f64 / (1u64 << 53) as f64;
+        min + unit * (max - min)
+    }
+}
+
+fn round1(value: f64) -> f64 {
+    (value * 10.0).round() / 10.0
+}
+
+fn round4(value: f64) -> f64 {
+    (value * 10_000.0).round() / 10_000.0
+}
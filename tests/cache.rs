@@ -0,0 +1,225 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::CacheConfig,
+    model::Location,
+    provider::{CacheStats, CachingProvider, DiskCache, WeatherProvider},
+};
+use std::{path::Path, sync::Arc, time::Duration};
+use support::counting::CountingProvider;
+
+fn cached(config: CacheConfig) -> (Arc<CountingProvider>, CachingProvider) {
+    let upstream = Arc::new(CountingProvider::new());
+    let provider = CachingProvider::new(upstream.clone(), &config);
+    (upstream, provider)
+}
+
+fn persisted(path: &Path, config: CacheConfig) -> (Arc<CountingProvider>, CachingProvider) {
+    let (upstream, provider) = cached(config);
+    let store = DiskCache::open(path, 10, Duration::ZERO).unwrap();
+    (upstream, provider.with_store(store))
+}
+
+#[tokio::test]
+async fn repeated_lookups_are_served_from_the_cache() {
+    let (upstream, provider) = cached(CacheConfig::default());
+
+    for query in ["London", "london", "  LONDON "] {
+        let location = provider.geocode(query).await.unwrap();
+        provider.current(&location).await.unwrap();
+        provider.forecast(&location, 24).await.unwrap();
+    }
+
+    assert_eq!(upstream.geocode_calls(), 1);
+    assert_eq!(upstream.current_calls(), 1);
+    assert_eq!(upstream.forecast_calls(), 1);
+
+    let metrics = provider.metrics();
+    assert_eq!(
+        metrics.geocode,
+        CacheStats {
+            hits: 2,
+            disk_hits: 0,
+            coalesced: 0,
+            stale: 0,
+            misses: 1,
+            evictions: 0,
+            entries: 1
+        }
+    );
+    assert_eq!(metrics.current.hits, 2);
+    assert_eq!(metrics.forecast.misses, 1);
+}
+
+#[tokio::test]
+async fn observations_keep_the_name_they_were_asked_for() {
+    let (upstream, provider) = cached(CacheConfig::default());
+    let springfield = Location {
+        name: "Springfield".to_string(),
+        country: Some("US".to_string()),
+        state: Some("Illinois".to_string()),
+        ..Location::at(39.799, -89.644)
+    };
+    let capital = Location {
+        name: "Illinois State Capitol".to_string(),
+        ..springfield.clone()
+    };
+
+    for location in [&springfield, &capital, &springfield] {
+        let observation = provider.current(location).await.unwrap();
+        assert_eq!(observation.location, location.name);
+    }
+    assert_eq!(upstream.current_calls(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn observations_expire_before_geocoding() {
+    let (upstream, provider) = cached(CacheConfig {
+        geocode_ttl: Duration::from_secs(3600),
+        weather_ttl: Duration::from_secs(60),
+        ..CacheConfig::default()
+    });
+
+    let location = provider.geocode("Oslo").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    tokio::time::advance(Duration::from_secs(61)).await;
+    let location = provider.geocode("Oslo").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    assert_eq!(upstream.geocode_calls(), 1);
+    assert_eq!(upstream.current_calls(), 2);
+}
+
+#[tokio::test]
+async fn full_cache_evicts_the_least_recently_used_entry() {
+    let (upstream, provider) = cached(CacheConfig {
+        max_entries: 2,
+        ..CacheConfig::default()
+    });
+
+    provider.geocode("Paris").await.unwrap();
+    provider.geocode("Rome").await.unwrap();
+    provider.geocode("Paris").await.unwrap();
+    provider.geocode("Berlin").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 3);
+
+    // Rome was least recently used, so it went; Paris is still cached.
+    provider.geocode("Paris").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 3);
+    provider.geocode("Rome").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 4);
+
+    let stats = provider.metrics().geocode;
+    assert_eq!(stats.entries, 2);
+    assert_eq!(stats.evictions, 2);
+}
+
+#[tokio::test]
+async fn errors_are_not_cached() {
+    let (upstream, provider) = cached(CacheConfig::default());
+
+    assert!(provider.geocode(CountingProvider::UNKNOWN).await.is_err());
+    assert!(provider.geocode(CountingProvider::UNKNOWN).await.is_err());
+    assert_eq!(upstream.geocode_calls(), 2);
+}
+
+#[tokio::test]
+async fn zero_ttl_disables_caching() {
+    let (upstream, provider) = cached(CacheConfig {
+        weather_ttl: Duration::ZERO,
+        ..CacheConfig::default()
+    });
+
+    let location = provider.geocode("Madrid").await.unwrap();
+    provider.current(&location).await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    assert_eq!(upstream.current_calls(), 2);
+    assert_eq!(provider.metrics().current, CacheStats::default());
+}
+
+#[tokio::test]
+async fn persisted_entries_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+
+    {
+        let (upstream, provider) = persisted(&path, CacheConfig::default());
+        let location = provider.geocode("Lisbon").await.unwrap();
+        provider.current(&location).await.unwrap();
+        assert_eq!(upstream.geocode_calls(), 1);
+    }
+
+    let (upstream, provider) = persisted(&path, CacheConfig::default());
+    let location = provider.geocode("lisbon").await.unwrap();
+    provider.current(&location).await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    assert_eq!(location.name, "Lisbon");
+    assert_eq!(upstream.geocode_calls(), 0);
+    assert_eq!(upstream.current_calls(), 0);
+    let metrics = provider.metrics();
+    assert_eq!(metrics.geocode.disk_hits, 1);
+    assert_eq!((metrics.current.disk_hits, metrics.current.hits), (1, 1));
+}
+
+#[tokio::test]
+async fn expired_persisted_entries_are_refetched() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    let config = CacheConfig {
+        weather_ttl: Duration::from_millis(50),
+        ..CacheConfig::default()
+    };
+
+    let (_, provider) = persisted(&path, config.clone());
+    let location = provider.geocode("Vienna").await.unwrap();
+    provider.current(&location).await.unwrap();
+    drop(provider);
+
+    std::thread::sleep(Duration::from_millis(100));
+    let (upstream, provider) = persisted(&path, config);
+    let location = provider.geocode("Vienna").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    assert_eq!(upstream.geocode_calls(), 0);
+    assert_eq!(upstream.current_calls(), 1);
+}
+
+#[tokio::test]
+async fn persisted_entries_are_bounded() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    let (_, provider) = cached(CacheConfig::default());
+    let provider = provider.with_store(DiskCache::open(&path, 2, Duration::ZERO).unwrap());
+
+    for city in ["Prague", "Warsaw", "Budapest"] {
+        provider.geocode(city).await.unwrap();
+    }
+    drop(provider);
+
+    let store = DiskCache::open(&path, 2, Duration::ZERO).unwrap();
+    assert_eq!(store.len("geocode"), 2);
+
+    // The oldest entry went first.
+    let (upstream, provider) = cached(CacheConfig::default());
+    let provider = provider.with_store(store);
+    provider.geocode("Budapest").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 0);
+    provider.geocode("Prague").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 1);
+}
+
+#[test]
+fn newer_schema_versions_are_rejected() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    rusqlite::Connection::open(&path)
+        .unwrap()
+        .pragma_update(None, "user_version", 99)
+        .unwrap();
+
+    let err = DiskCache::open(&path, 10, Duration::ZERO).err().unwrap();
+    assert!(err.contains("schema version 99"), "{err}");
+}
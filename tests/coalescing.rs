@@ -0,0 +1,97 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::CacheConfig,
+    error::WeatherError,
+    model::Observation,
+    provider::{CachingProvider, WeatherProvider},
+};
+use std::{sync::Arc, time::Duration};
+use support::counting::CountingProvider;
+use tokio::task::JoinSet;
+
+const CALLERS: usize = 8;
+
+fn coalescing(config: CacheConfig) -> (Arc<CountingProvider>, Arc<CachingProvider>) {
+    let upstream = Arc::new(CountingProvider::new().with_delay(Duration::from_millis(50)));
+    let provider = Arc::new(CachingProvider::new(upstream.clone(), &config));
+    (upstream, provider)
+}
+
+/// Runs a `get-weather` style lookup for `city` from `CALLERS` tasks at once.
+async fn fan_out(
+    provider: &Arc<CachingProvider>,
+    city: &'static str,
+) -> Vec<Result<Observation, WeatherError>> {
+    let mut tasks = JoinSet::new();
+    for _ in 0..CALLERS {
+        let provider = provider.clone();
+        tasks.spawn(async move {
+            let location = provider.geocode(city).await?;
+            provider.current(&location).await
+        });
+    }
+    tasks.join_all().await
+}
+
+#[tokio::test(start_paused = true)]
+async fn concurrent_lookups_share_one_upstream_request() {
+    let (upstream, provider) = coalescing(CacheConfig::default());
+
+    let results = fan_out(&provider, "Dublin").await;
+    let first = results[0].as_ref().unwrap();
+    assert!(results.iter().all(|r| r.as_ref() == Ok(first)));
+
+    assert_eq!(upstream.geocode_calls(), 1);
+    assert_eq!(upstream.current_calls(), 1);
+    let metrics = provider.metrics();
+    assert_eq!(metrics.geocode.coalesced, CALLERS as u64 - 1);
+    assert_eq!(metrics.current.coalesced, CALLERS as u64 - 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn concurrent_callers_all_receive_the_error() {
+    let (upstream, provider) = coalescing(CacheConfig::default());
+
+    let results = fan_out(&provider, CountingProvider::UNKNOWN).await;
+    for result in results {
+        assert_eq!(
+            result.unwrap_err(),
+            WeatherError::CityNotFound(CountingProvider::UNKNOWN.to_string())
+        );
+    }
+    assert_eq!(upstream.geocode_calls(), 1);
+
+    // The failed flight is forgotten, so the next lookup tries again.
+    assert!(provider.geocode(CountingProvider::UNKNOWN).await.is_err());
+    assert_eq!(upstream.geocode_calls(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn different_locations_are_not_coalesced() {
+    let (upstream, provider) = coalescing(CacheConfig::default());
+
+    let (a, b) = tokio::join!(fan_out(&provider, "Cairo"), fan_out(&provider, "Lima"));
+    assert!(a.iter().chain(&b).all(Result::is_ok));
+    assert_ne!(a[0], b[0]);
+
+    assert_eq!(upstream.geocode_calls(), 2);
+    assert_eq!(upstream.current_calls(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn coalescing_applies_without_caching() {
+    let (upstream, provider) = coalescing(CacheConfig {
+        geocode_ttl: Duration::ZERO,
+        weather_ttl: Duration::ZERO,
+        ..CacheConfig::default()
+    });
+
+    fan_out(&provider, "Nairobi").await;
+    assert_eq!(upstream.geocode_calls(), 1);
+    assert_eq!(upstream.current_calls(), 1);
+
+    // Nothing was cached, so a later lookup goes upstream again.
+    provider.geocode("Nairobi").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 2);
+}
@@ -0,0 +1,39 @@
+use get_weather_poisoned::model::{Exposure, PollenKind};
+
+fn uv_risk(index: f64) -> String {
+    Exposure::uv(index, "12:00".to_string()).risk
+}
+
+fn pollen_risk(kind: PollenKind, grains: f64) -> String {
+    Exposure::pollen(kind, grains, "12:00".to_string()).risk
+}
+
+#[test]
+fn uv_index_follows_the_who_scale() {
+    let risks: Vec<String> = [0.0, 2.9, 3.0, 6.0, 8.0, 10.9, 11.0]
+        .into_iter()
+        .map(uv_risk)
+        .collect();
+    assert_eq!(
+        risks,
+        [
+            "Low",
+            "Low",
+            "Moderate",
+            "High",
+            "Very high",
+            "Very high",
+            "Extreme"
+        ]
+    );
+}
+
+#[test]
+fn pollen_thresholds_depend_on_the_kind() {
+    assert_eq!(pollen_risk(PollenKind::Grass, 0.0), "None");
+    assert_eq!(pollen_risk(PollenKind::Grass, 20.0), "High");
+    assert_eq!(pollen_risk(PollenKind::Tree, 20.0), "Moderate");
+    assert_eq!(pollen_risk(PollenKind::Weed, 20.0), "Moderate");
+    assert_eq!(pollen_risk(PollenKind::Weed, 500.0), "Very high");
+    assert_eq!(pollen_risk(PollenKind::Tree, 1499.0), "High");
+}
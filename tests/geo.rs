@@ -0,0 +1,84 @@
+use get_weather_poisoned::geo::Geometry;
+use serde_json::Value;
+
+/// Springfield, Illinois, which the fixture's alert areas are drawn around.
+const LAT: f64 = 39.7990175;
+const LON: f64 = -89.6439575;
+
+/// The geometry of the fixture alert whose ID ends in `name`.
+fn area(name: &str) -> Geometry {
+    let alerts: Value = serde_json::from_str(include_str!("fixtures/nws_alerts.json")).unwrap();
+    let feature = alerts["features"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .find(|feature| feature["id"].as_str().unwrap().ends_with(name))
+        .unwrap();
+    serde_json::from_value(feature["geometry"].clone()).unwrap()
+}
+
+#[test]
+fn polygon_contains_points_inside_it_only() {
+    let tornado = area("tornado");
+    assert!(matches!(tornado, Geometry::Polygon(_)));
+    assert!(tornado.contains(LAT, LON));
+    assert!(!tornado.contains(LAT, -89.3));
+    assert!(!tornado.contains(40.2, LON));
+
+    assert!(!area("thunderstorm").contains(LAT, LON));
+}
+
+#[test]
+fn holes_are_cut_out_of_polygons() {
+    let frost = area("frost");
+    assert!(!frost.contains(LAT, LON));
+    assert!(frost.contains(39.6, -89.9));
+}
+
+#[test]
+fn multipolygon_contains_points_in_any_part() {
+    let flood = area("flood");
+    assert!(matches!(flood, Geometry::MultiPolygon(ref parts) if parts.len() == 2));
+    assert!(flood.contains(LAT, LON));
+    assert!(flood.contains(40.6, -90.3));
+    assert!(!flood.contains(40.2, -89.6));
+}
+
+#[test]
+fn concave_rings_follow_their_edges() {
+    // A "C" opening east: the notch between its arms is outside.
+    let shape: Geometry = serde_json::from_value(serde_json::json!({
+        "type": "Polygon",
+        "coordinates": [[[0, 0], [3, 0], [3, 1], [1, 1], [1, 2], [3, 2], [3, 3], [0, 3], [0, 0]]]
+    }))
+    .unwrap();
+    assert!(shape.contains(0.5, 2.5));
+    assert!(shape.contains(2.5, 0.5));
+    assert!(shape.contains(1.5, 0.5));
+    assert!(!shape.contains(1.5, 2.0));
+    assert!(!shape.contains(1.5, 4.0));
+}
+
+#[test]
+fn other_geometries_contain_nothing() {
+    let statement = area("statement");
+    assert_eq!(statement, Geometry::Other);
+    assert!(!statement.contains(LAT, LON));
+}
+
+#[test]
+fn altitudes_are_ignored() {
+    let shape: Geometry = serde_json::from_value(serde_json::json!({
+        "type": "MultiPolygon",
+        "coordinates": [[[[0, 0, 120.5], [2, 0, 98], [2, 2, 101], [0, 2, 110], [0, 0, 120.5]]]]
+    }))
+    .unwrap();
+    assert!(shape.contains(1.0, 1.0));
+    assert!(!shape.contains(3.0, 1.0));
+
+    let short = serde_json::from_value::<Geometry>(serde_json::json!({
+        "type": "Polygon",
+        "coordinates": [[[0], [2, 0], [2, 2], [0]]]
+    }));
+    assert!(short.is_err());
+}
@@ -0,0 +1,747 @@
+mod support;
+
+use get_weather_poisoned::{
+    model::Observation,
+    provider::{NwsAlertFeed, NwsAlerts, OpenMeteoProvider, SyntheticProvider},
+};
+use rmcp::model::{ErrorCode, PaginatedRequestParam};
+use serde_json::json;
+use std::sync::Arc;
+use support::counting::CountingProvider;
+use support::mcp::{json, mcp_error, text, McpHarness};
+use support::mock_upstream::{ambiguous_geocoding, Endpoint, MockResponse, MockUpstream};
+
+/// A harness backed by the mock's OpenWeatherMap API, which knows three Springfields.
+async fn springfields(mock: &MockUpstream) -> McpHarness {
+    mock.respond_with(Endpoint::Geocode, MockResponse::Json(ambiguous_geocoding()));
+    McpHarness::with_openweathermap(mock).await
+}
+
+#[tokio::test]
+async fn initialize_reports_provider_and_tools_capability() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let info = harness.client.peer_info();
+    assert!(info.capabilities.tools.is_some());
+    assert!(info
+        .instructions
+        .as_deref()
+        .unwrap_or_default()
+        .contains("fixture"));
+}
+
+#[tokio::test]
+async fn list_tools_exposes_every_tool() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let tools = harness
+        .client
+        .list_tools(PaginatedRequestParam::default())
+        .await
+        .unwrap()
+        .tools;
+    let names: Vec<&str> = tools.iter().map(|t| t.name.as_ref()).collect();
+    assert_eq!(
+        names,
+        [
+            "get-weather",
+            "get-forecast",
+            "get-air-quality",
+            "get-uv-pollen",
+            "get-nowcast",
+            "get-historical-weather",
+            "get-alerts",
+            "resolve-location",
+            "reverse-geocode"
+        ]
+    );
+    for tool in &tools {
+        assert_eq!(tool.input_schema.get("type"), Some(&json!("object")));
+    }
+}
+
+#[tokio::test]
+async fn weather_with_valid_arguments() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call("get-weather", Some(json!({ "city": "London" })))
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).starts_with("The temperature in London is 8.4°C"));
+    assert_eq!(mock.request_count(Endpoint::Weather), 1);
+}
+
+#[tokio::test]
+async fn weather_returns_a_summary_and_the_observation() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call("get-weather", Some(json!({ "city": "London" })))
+        .await
+        .unwrap();
+    let [summary, observation] = result.content.as_slice() else {
+        panic!(
+            "expected a summary and a JSON block, got {:?}",
+            result.content
+        );
+    };
+    let observation: Observation =
+        serde_json::from_str(&observation.raw.as_text().unwrap().text).unwrap();
+    assert_eq!(observation.location, "London");
+    assert_eq!(observation.country.as_deref(), Some("GB"));
+    assert_eq!(observation.temperature.current, 8.4);
+    assert_eq!(observation.humidity, Some(82.0));
+    assert_eq!(observation.conditions[0].description, "light rain");
+    assert_eq!(summary.raw.as_text().unwrap().text, observation.summary());
+}
+
+#[tokio::test]
+async fn weather_without_a_location_is_invalid_params() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let err = harness
+        .call("get-weather", Some(json!({ "candidates": false })))
+        .await
+        .unwrap_err();
+    let error = mcp_error(err);
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert!(error
+        .message
+        .starts_with("Invalid arguments for get-weather"));
+    assert_eq!(error.data.unwrap()["code"], "invalid_arguments");
+}
+
+#[tokio::test]
+async fn forecast_with_valid_arguments() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "city": "London", "hours": 9 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).starts_with("Forecast for London (next 9 hours)"));
+
+    let entries = json(&result);
+    assert_eq!(entries.as_array().unwrap().len(), 3);
+    assert_eq!(entries[0]["conditions"], "light rain");
+}
+
+#[tokio::test]
+async fn forecast_horizon_is_clamped() {
+    let harness = McpHarness::connect(Arc::new(SyntheticProvider::new(7))).await;
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "city": "Oslo", "hours": 1000 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(json(&result).as_array().unwrap().len(), 40);
+}
+
+#[tokio::test]
+async fn forecast_for_unknown_city_is_a_tool_error() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let result = harness
+        .call("get-forecast", Some(json!({ "city": "Atlantis" })))
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert!(text(&result).starts_with("City not found: Atlantis"));
+    let error = &json(&result)["error"];
+    assert_eq!(error["code"], "city_not_found");
+    assert_eq!(error["retryable"], false);
+}
+
+#[tokio::test]
+async fn resolve_location_lists_candidates_with_ids() {
+    let mock = MockUpstream::start().await;
+    let harness = springfields(&mock).await;
+
+    let result = harness
+        .call(
+            "resolve-location",
+            Some(json!({ "city": "Springfield", "limit": 2 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).contains("1. Springfield, Illinois, US (39.7990, -89.6440)"));
+
+    let candidates = json(&result)["candidates"].clone();
+    assert_eq!(candidates.as_array().unwrap().len(), 2);
+    assert_eq!(
+        candidates[1]["id"],
+        "37.2082,-93.2923;US;Missouri;Springfield"
+    );
+    assert_eq!(candidates[1]["state"], "Missouri");
+    assert_eq!(candidates[1]["country"], "US");
+}
+
+#[tokio::test]
+async fn resolve_location_caps_the_limit_at_what_the_provider_lists() {
+    let mock = MockUpstream::start().await;
+    // Six places, one more than OpenWeatherMap ever lists.
+    let places: Vec<_> = (0..6)
+        .map(|i| {
+            let lat = 40.0 + f64::from(i);
+            json!({ "name": "Springfield", "lat": lat, "lon": -90.0, "country": "US" })
+        })
+        .collect();
+    mock.respond_with(Endpoint::Geocode, MockResponse::Json(json!(places)));
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let tools = harness
+        .client
+        .list_tools(PaginatedRequestParam::default())
+        .await
+        .unwrap()
+        .tools;
+    let resolve = tools.iter().find(|t| t.name == "resolve-location").unwrap();
+    assert_eq!(resolve.input_schema["properties"]["limit"]["maximum"], 5);
+
+    let result = harness
+        .call(
+            "resolve-location",
+            Some(json!({ "city": "Springfield", "limit": 8 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(json(&result)["candidates"].as_array().unwrap().len(), 5);
+    let query = &mock.requests()[0].query;
+    assert!(query.contains("limit=5"), "{query}");
+}
+
+#[tokio::test]
+async fn resolve_location_ranks_the_requested_state_first() {
+    let mock = MockUpstream::start().await;
+    let harness = springfields(&mock).await;
+
+    let result = harness
+        .call(
+            "resolve-location",
+            Some(json!({ "city": "Springfield", "state": "missouri", "country": "us" })),
+        )
+        .await
+        .unwrap();
+    let candidates = json(&result)["candidates"].clone();
+    assert_eq!(candidates.as_array().unwrap().len(), 3);
+    assert_eq!(candidates[0]["state"], "Missouri");
+    assert!(mock.requests()[0]
+        .query
+        .contains("q=Springfield%2Cmissouri%2Cus"));
+}
+
+#[tokio::test]
+async fn resolve_location_drops_candidates_outside_the_country() {
+    let mock = MockUpstream::start().await;
+    let harness = springfields(&mock).await;
+
+    let result = harness
+        .call(
+            "resolve-location",
+            Some(json!({ "city": "Springfield", "country": "AU" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert!(text(&result).starts_with("City not found: Springfield, AU"));
+
+    let err = harness
+        .call(
+            "resolve-location",
+            Some(json!({ "city": "Springfield", "country": "Australia" })),
+        )
+        .await
+        .unwrap_err();
+    let error = mcp_error(err);
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert!(error.message.contains("'country' must be a two-letter"));
+}
+
+#[tokio::test]
+async fn get_weather_can_list_candidates_instead() {
+    let mock = MockUpstream::start().await;
+    let harness = springfields(&mock).await;
+
+    let result = harness
+        .call(
+            "get-weather",
+            Some(json!({ "city": "Springfield", "candidates": true })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(json(&result)["candidates"].as_array().unwrap().len(), 3);
+    assert_eq!(mock.request_count(Endpoint::Weather), 0);
+}
+
+#[tokio::test]
+async fn coordinates_skip_geocoding() {
+    let provider = Arc::new(CountingProvider::new());
+    let harness = McpHarness::connect(provider.clone()).await;
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "lat": 59.9139, "lon": 10.7522, "hours": 3 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).starts_with("Forecast for 59.9139, 10.7522"));
+    assert_eq!(provider.geocode_calls(), 0);
+    assert_eq!(provider.forecast_calls(), 1);
+}
+
+#[tokio::test]
+async fn location_ids_resolve_without_geocoding() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "location_id": "51.5073,-0.1276", "hours": 3 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(json(&result)[0]["conditions"], "light rain");
+}
+
+#[tokio::test]
+async fn location_ids_keep_the_place_name() {
+    let mock = MockUpstream::start().await;
+    let harness = springfields(&mock).await;
+
+    let result = harness
+        .call("resolve-location", Some(json!({ "city": "Springfield" })))
+        .await
+        .unwrap();
+    let id = json(&result)["candidates"][1]["id"].clone();
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "location_id": id, "hours": 3 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).starts_with("Forecast for Springfield, Missouri, US (next 3 hours)"));
+    assert_eq!(mock.request_count(Endpoint::Geocode), 1);
+}
+
+#[tokio::test]
+async fn postal_codes_are_geocoded_within_their_country() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "postal_code": "E14", "country": "gb" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert!(text(&result).starts_with("Forecast for E14, GB"));
+    assert_eq!(mock.request_count(Endpoint::Zip), 1);
+    assert_eq!(mock.request_count(Endpoint::Geocode), 0);
+
+    let fixtures = McpHarness::with_fixtures().await;
+    let result = fixtures
+        .call(
+            "get-forecast",
+            Some(json!({ "postal_code": "E14", "country": "GB" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(json(&result)["error"]["code"], "unsupported");
+}
+
+#[tokio::test]
+async fn location_must_be_given_exactly_one_way() {
+    let harness = McpHarness::with_fixtures().await;
+
+    for (arguments, problem) in [
+        (json!({ "hours": 3 }), "give exactly one of"),
+        (
+            json!({ "city": "London", "lat": 51.5, "lon": -0.1 }),
+            "give exactly one of",
+        ),
+        (
+            json!({ "lat": 51.5 }),
+            "'lat' and 'lon' must be given together",
+        ),
+        (json!({ "lat": 91, "lon": 0 }), "'lat' must be between"),
+        (
+            json!({ "postal_code": "E14" }),
+            "'postal_code' needs a 'country'",
+        ),
+        (
+            json!({ "lat": 51.5, "lon": -0.1, "state": "England" }),
+            "'state' can only qualify 'city'",
+        ),
+        (
+            json!({ "location_id": "London" }),
+            "'location_id' must be an ID",
+        ),
+        (
+            json!({ "location_id": format!("51.5,-0.1;GB;;{}", "x".repeat(101)) }),
+            "'location_id' must be an ID",
+        ),
+        (
+            json!({ "location_id": "51.5,-0.1;GBR;England;London" }),
+            "'location_id' must be an ID",
+        ),
+    ] {
+        let err = harness
+            .call("get-forecast", Some(arguments.clone()))
+            .await
+            .unwrap_err();
+        let error = mcp_error(err);
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS, "{arguments}");
+        assert!(error.message.contains(problem), "{}", error.message);
+    }
+}
+
+#[tokio::test]
+async fn reverse_geocode_names_the_place() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call(
+            "reverse-geocode",
+            Some(json!({ "lat": 51.5, "lon": -0.12 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    assert_eq!(
+        text(&result).lines().next(),
+        Some("51.5000, -0.1200 is in London, England, GB.")
+    );
+    let location = json(&result);
+    assert_eq!(location["id"], "51.5000,-0.1200;GB;England;London");
+    assert_eq!(location["name"], "London");
+
+    let err = harness
+        .call("reverse-geocode", Some(json!({ "lat": 51.5, "lon": 200 })))
+        .await
+        .unwrap_err();
+    assert_eq!(mcp_error(err).code, ErrorCode::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn reverse_geocode_is_unsupported_without_a_reverse_endpoint() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let result = harness
+        .call(
+            "reverse-geocode",
+            Some(json!({ "lat": 51.5, "lon": -0.12 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(
+        text(&result).lines().next(),
+        Some("The fixture provider does not support reverse geocoding")
+    );
+    assert_eq!(json(&result)["error"]["code"], "unsupported");
+}
+
+#[tokio::test]
+async fn air_quality_reports_index_category_and_pollutants() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call(
+            "get-air-quality",
+            Some(json!({ "city": "London", "hours": 1 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    let text = text(&result);
+    assert!(
+        text.starts_with(
+            "Air quality in London: AQI 2 (Fair, OpenWeatherMap 1-5 scale); \
+             PM2.5 3.2, PM10 4.1, O3 68.66, NO2 12.6, SO2 1.6, CO 201.94 µg/m³."
+        ),
+        "{text}"
+    );
+    assert!(
+        text.contains("2024-01-01 13:00:00: AQI 3 (Moderate"),
+        "{text}"
+    );
+
+    let report = json(&result);
+    assert_eq!(report["current"]["scale"], "openweathermap");
+    assert_eq!(report["forecast"].as_array().unwrap().len(), 1);
+}
+
+#[tokio::test]
+async fn alerts_list_severity_timing_and_instructions() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Geocode, MockResponse::Json(ambiguous_geocoding()));
+    let feed = NwsAlertFeed::new().with_base_url(mock.base_url());
+    let provider = NwsAlerts::new(Arc::new(mock.openweathermap()), Arc::new(feed));
+    let harness = McpHarness::connect(Arc::new(provider)).await;
+
+    let result = harness
+        .call(
+            "get-alerts",
+            Some(json!({ "city": "Springfield", "state": "Illinois", "country": "US" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    let text = text(&result);
+    assert!(
+        text.starts_with(
+            "4 active alerts for Springfield, Illinois, US:\n\
+             [Extreme] Tornado Warning, from 2025-07-14T16:10:00-05:00 until 2025-07-14T16:30:00-05:00\n  \
+             Tornado Warning issued July 14"
+        ),
+        "{text}"
+    );
+    assert!(
+        text.contains(
+            "Instructions: TAKE COVER NOW! Move to a basement or an interior room on the lowest floor of a sturdy building."
+        ),
+        "{text}"
+    );
+
+    let alerts = json(&result)["alerts"].clone();
+    assert_eq!(alerts.as_array().unwrap().len(), 4);
+    assert_eq!(alerts[1]["event"], "Flood Warning");
+    assert_eq!(alerts[1]["severity"], "Moderate");
+    assert_eq!(alerts[2]["event"], "Special Weather Statement");
+    assert_eq!(alerts[2]["matched_by"], "zone");
+}
+
+#[tokio::test]
+async fn alerts_are_unsupported_without_an_alert_source() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let result = harness
+        .call("get-alerts", Some(json!({ "city": "London" })))
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(true));
+    assert_eq!(json(&result)["error"]["code"], "unsupported");
+}
+
+#[tokio::test]
+async fn uv_pollen_reports_peaks_with_risk_categories() {
+    let mock = MockUpstream::start().await;
+    let provider = OpenMeteoProvider::new().with_base_url(mock.base_url());
+    let harness = McpHarness::connect(Arc::new(provider)).await;
+
+    let result = harness
+        .call(
+            "get-uv-pollen",
+            Some(json!({ "lat": 52.52, "lon": 13.41, "days": 9 })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    let text = text(&result);
+    assert!(
+        text.starts_with(
+            "UV index and pollen for 52.5200, 13.4100:\n\
+             2024-05-01: UV 6.1 (High) at 13:00; grass 35 grains/m³ (High) at 15:00; \
+             tree 120 grains/m³ (High) at 11:00; weed 3 grains/m³ (Low) at 18:00\n\
+             2024-05-02: UV 6.1 (High) at 13:00\n"
+        ),
+        "{text}"
+    );
+    assert_eq!(json(&result)[0]["uv"]["risk"], "High");
+    // More days than are forecast are capped.
+    assert!(mock.requests()[0].query.contains("forecast_days=4"));
+}
+
+#[tokio::test]
+async fn nowcast_summarises_the_next_hour() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let result = harness
+        .call("get-nowcast", Some(json!({ "city": "London" })))
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    // Minutes are counted from the call, which may be some seconds after the
+    // first minute the mock reports.
+    let text = text(&result);
+    assert!(
+        text.starts_with(
+            "Precipitation in London over the next hour: Light precipitation starting in ~1"
+        ),
+        "{text}"
+    );
+    assert!(text.contains(", lasting ~20 minutes."), "{text}");
+
+    let nowcast = json(&result);
+    assert_eq!(nowcast["resolution_minutes"], 1);
+    assert!(nowcast["steps"].as_array().unwrap().len() >= 60);
+}
+
+#[tokio::test]
+async fn historical_weather_aggregates_past_days() {
+    let mock = MockUpstream::start().await;
+    let provider = OpenMeteoProvider::new().with_base_url(mock.base_url());
+    let harness = McpHarness::connect(Arc::new(provider)).await;
+
+    let result = harness
+        .call(
+            "get-historical-weather",
+            Some(json!({ "lat": 51.5073, "lon": -0.1276, "date": "2024-01-01" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    let text = text(&result);
+    assert!(
+        text.starts_with(
+            "Weather in 51.5073, -0.1276 on 2024-01-01:\n\
+             2024-01-01: 0.0 to 11.5°C (mean 5.8°C), 2.0 mm precipitation, mostly light rain\n"
+        ),
+        "{text}"
+    );
+    assert_eq!(json(&result)[0]["temp_mean"], 5.75);
+}
+
+#[tokio::test]
+async fn historical_weather_rejects_dates_it_cannot_serve() {
+    let harness = McpHarness::with_fixtures().await;
+    let london = |dates: serde_json::Value| {
+        let mut args = json!({ "city": "London" });
+        args.as_object_mut()
+            .unwrap()
+            .extend(dates.as_object().unwrap().clone());
+        Some(args)
+    };
+
+    for (dates, problem) in [
+        (
+            json!({ "date": "01/02/2024" }),
+            "'date' must be a date like 2024-01-31",
+        ),
+        (
+            json!({ "date": "2999-01-01" }),
+            "dates must be before today",
+        ),
+        (
+            json!({ "start_date": "2024-01-01" }),
+            "'start_date' and 'end_date' must be given together",
+        ),
+        (
+            json!({ "date": "2024-01-01", "end_date": "2024-01-02" }),
+            "give either 'date' or 'start_date' and 'end_date'",
+        ),
+        (
+            json!({ "start_date": "2024-01-05", "end_date": "2024-01-01" }),
+            "'end_date' must not be before 'start_date'",
+        ),
+        (
+            json!({ "start_date": "2024-01-01", "end_date": "2024-02-01" }),
+            "a range can span at most 31 days",
+        ),
+    ] {
+        let err = harness
+            .call("get-historical-weather", london(dates))
+            .await
+            .unwrap_err();
+        let error = mcp_error(err);
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+        assert!(error.message.contains(problem), "{}", error.message);
+    }
+
+    // A valid range reaches the provider, which has no history.
+    let result = harness
+        .call(
+            "get-historical-weather",
+            london(json!({ "start_date": "2024-01-01", "end_date": "2024-01-31" })),
+        )
+        .await
+        .unwrap();
+    assert_eq!(json(&result)["error"]["code"], "unsupported");
+}
+
+#[tokio::test]
+async fn missing_arguments_are_invalid_params() {
+    let harness = McpHarness::with_fixtures().await;
+
+    for tool in [
+        "get-weather",
+        "get-forecast",
+        "get-air-quality",
+        "get-uv-pollen",
+        "get-nowcast",
+        "get-historical-weather",
+        "get-alerts",
+        "resolve-location",
+        "reverse-geocode",
+    ] {
+        let err = harness.call(tool, None).await.unwrap_err();
+        let error = mcp_error(err);
+        assert_eq!(error.code, ErrorCode::INVALID_PARAMS, "{tool}");
+        assert!(error.message.starts_with("Invalid arguments"), "{tool}");
+        assert_eq!(error.data.unwrap()["code"], "invalid_arguments");
+    }
+}
+
+#[tokio::test]
+async fn invalid_arguments_are_invalid_params() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let err = harness
+        .call("get-forecast", Some(json!({ "city": 42 })))
+        .await
+        .unwrap_err();
+    let error = mcp_error(err);
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert!(error
+        .message
+        .starts_with("Invalid arguments for get-forecast"));
+
+    let err = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "city": "London", "hours": -3 })),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(mcp_error(err).code, ErrorCode::INVALID_PARAMS);
+}
+
+#[tokio::test]
+async fn unknown_tool_is_invalid_params() {
+    let harness = McpHarness::with_fixtures().await;
+
+    let err = harness
+        .call("get-tides", Some(json!({})))
+        .await
+        .unwrap_err();
+    let error = mcp_error(err);
+    assert_eq!(error.code, ErrorCode::INVALID_PARAMS);
+    assert_eq!(error.message, "Unknown tool: get-tides");
+}
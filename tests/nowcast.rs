@@ -0,0 +1,85 @@
+use get_weather_poisoned::model::{Nowcast, NowcastStep};
+
+const NOW: i64 = 1_704_110_400;
+
+/// A minute-by-minute nowcast from `NOW` with the given rate per minute.
+fn minutely(rates: impl IntoIterator<Item = f64>) -> Nowcast {
+    Nowcast {
+        resolution_minutes: 1,
+        steps: rates
+            .into_iter()
+            .enumerate()
+            .map(|(minute, intensity)| NowcastStep {
+                time: NOW + minute as i64 * 60,
+                intensity,
+            })
+            .collect(),
+    }
+}
+
+/// 60 minutes, wet at `rate` mm/h during `wet`.
+fn spell(wet: std::ops::Range<usize>, rate: f64) -> Nowcast {
+    minutely((0..60).map(|minute| if wet.contains(&minute) { rate } else { 0.0 }))
+}
+
+#[test]
+fn summary_times_the_first_spell() {
+    assert_eq!(
+        spell(12..32, 1.2).summary(NOW),
+        "Light precipitation starting in ~12 minutes, lasting ~20 minutes"
+    );
+    assert_eq!(
+        spell(0..25, 4.0).summary(NOW),
+        "Moderate precipitation now, stopping in ~25 minutes"
+    );
+    assert_eq!(
+        spell(40..60, 12.0).summary(NOW),
+        "Heavy precipitation starting in ~40 minutes, lasting at least 20 minutes"
+    );
+    assert_eq!(
+        spell(0..60, 80.0).summary(NOW),
+        "Violent precipitation now, continuing for at least 60 minutes"
+    );
+}
+
+#[test]
+fn trace_amounts_count_as_dry() {
+    assert_eq!(
+        spell(10..20, 0.05).summary(NOW),
+        "No precipitation expected in the next 60 minutes"
+    );
+}
+
+#[test]
+fn summary_reads_from_now_when_the_nowcast_is_older() {
+    // Fetched five minutes ago: the spell now starts seven minutes from now.
+    let later = NOW + 5 * 60;
+    let nowcast = spell(12..32, 1.2).upcoming(later, 60);
+    assert_eq!(nowcast.steps.first().unwrap().time, later);
+    assert_eq!(nowcast.steps.len(), 55);
+    assert_eq!(
+        nowcast.summary(later),
+        "Light precipitation starting in ~7 minutes, lasting ~20 minutes"
+    );
+}
+
+#[test]
+fn upcoming_keeps_the_step_in_progress() {
+    let quarters = Nowcast {
+        resolution_minutes: 15,
+        steps: (0..6)
+            .map(|i| NowcastStep {
+                time: NOW + i * 15 * 60,
+                intensity: if i == 2 { 1.0 } else { 0.0 },
+            })
+            .collect(),
+    };
+
+    let nowcast = quarters.upcoming(NOW + 10 * 60, 60);
+    let times: Vec<i64> = nowcast.steps.iter().map(|s| s.time).collect();
+    assert_eq!(times, [NOW, NOW + 900, NOW + 1800, NOW + 2700, NOW + 3600]);
+    assert_eq!(
+        nowcast.summary(NOW + 10 * 60),
+        "Light precipitation starting in ~20 minutes, lasting ~15 minutes"
+    );
+}
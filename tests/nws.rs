@@ -0,0 +1,145 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::RetryConfig,
+    error::WeatherError,
+    model::{AlertMatch, Location},
+    provider::{BreakerState, NwsAlertFeed, NwsAlerts, ResilientProvider, WeatherProvider},
+};
+use serde_json::json;
+use std::sync::Arc;
+use support::mock_upstream::{Endpoint, MockResponse, MockUpstream};
+
+fn provider(mock: &MockUpstream) -> NwsAlerts {
+    NwsAlerts::new(Arc::new(mock.openweathermap()), Arc::new(feed(mock)))
+}
+
+fn feed(mock: &MockUpstream) -> NwsAlertFeed {
+    NwsAlertFeed::new().with_base_url(mock.base_url())
+}
+
+fn springfield() -> Location {
+    Location {
+        name: "Springfield".to_string(),
+        country: Some("US".to_string()),
+        state: Some("Illinois".to_string()),
+        lat: 39.7990175,
+        lon: -89.6439575,
+    }
+}
+
+#[tokio::test]
+async fn alerts_keep_only_areas_containing_the_point() {
+    let mock = MockUpstream::start().await;
+
+    let alerts = provider(&mock).alerts(&springfield()).await.unwrap();
+
+    let events: Vec<&str> = alerts.iter().map(|a| a.event.as_str()).collect();
+    assert_eq!(
+        events,
+        [
+            "Tornado Warning",
+            "Flood Warning",
+            "Special Weather Statement",
+            "Heat Advisory"
+        ]
+    );
+    let matched: Vec<AlertMatch> = alerts.iter().map(|a| a.matched_by).collect();
+    assert_eq!(
+        matched,
+        [
+            AlertMatch::Polygon,
+            AlertMatch::Polygon,
+            AlertMatch::Zone,
+            AlertMatch::Zone
+        ]
+    );
+
+    // The hazard's end is preferred over the message's expiry.
+    let heat = &alerts[3];
+    assert_eq!(heat.onset.as_deref(), Some("2025-07-14T12:00:00-05:00"));
+    assert_eq!(heat.expires.as_deref(), Some("2025-07-15T20:00:00-05:00"));
+    assert_eq!(heat.sender.as_deref(), Some("NWS Lincoln IL"));
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/alerts/active");
+    assert_eq!(requests[0].query, "point=39.7990%2C-89.6440");
+}
+
+#[tokio::test]
+async fn alerts_pass_everything_else_to_the_weather_provider() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+
+    let location = provider.geocode("London").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    assert_eq!(provider.name(), "openweathermap");
+    assert_eq!(mock.request_count(Endpoint::Weather), 1);
+    assert_eq!(mock.request_count(Endpoint::Alerts), 0);
+}
+
+#[tokio::test]
+async fn alerts_outside_the_united_states_are_unsupported() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+
+    let london = provider.geocode("London").await.unwrap();
+    let err = provider.alerts(&london).await.unwrap_err();
+    assert!(matches!(
+        err,
+        WeatherError::Unsupported {
+            provider: "nws",
+            ..
+        }
+    ));
+    assert_eq!(mock.request_count(Endpoint::Alerts), 0);
+
+    // Without a country, NWS rejecting the point decides.
+    mock.respond_with(
+        Endpoint::Alerts,
+        MockResponse::Status(400, json!({ "title": "Bad Request" })),
+    );
+    let err = provider
+        .alerts(&Location::at(48.85, 2.35))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), "unsupported");
+    assert_eq!(mock.request_count(Endpoint::Alerts), 1);
+}
+
+#[tokio::test]
+async fn an_alerts_outage_leaves_the_weather_breaker_closed() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(
+        Endpoint::Alerts,
+        MockResponse::Status(503, json!({ "title": "Service Unavailable" })),
+    );
+    let policy = RetryConfig {
+        max_attempts: 1,
+        breaker_threshold: 1,
+        ..RetryConfig::default()
+    };
+    let weather = Arc::new(ResilientProvider::new(
+        Arc::new(mock.openweathermap()),
+        &policy,
+    ));
+    let feed = Arc::new(ResilientProvider::new(Arc::new(feed(&mock)), &policy));
+    let provider = NwsAlerts::new(weather.clone(), feed.clone());
+
+    let err = provider.alerts(&springfield()).await.unwrap_err();
+    assert_eq!(err.code(), "upstream_unavailable");
+    let err = provider.alerts(&springfield()).await.unwrap_err();
+    assert!(matches!(
+        err,
+        WeatherError::CircuitOpen {
+            provider: "nws",
+            ..
+        }
+    ));
+    assert_eq!(feed.breaker_state(), BreakerState::Open);
+
+    provider.current(&springfield()).await.unwrap();
+    assert_eq!(weather.breaker_state(), BreakerState::Closed);
+}
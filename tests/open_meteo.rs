@@ -0,0 +1,172 @@
+mod support;
+
+use chrono::{Days, NaiveDate, Utc};
+use get_weather_poisoned::{
+    error::WeatherError,
+    model::Location,
+    provider::{OpenMeteoProvider, WeatherProvider},
+};
+use serde_json::json;
+use support::mock_upstream::{Endpoint, MockResponse, MockUpstream};
+
+fn provider(mock: &MockUpstream) -> OpenMeteoProvider {
+    OpenMeteoProvider::new().with_base_url(mock.base_url())
+}
+
+fn date(s: &str) -> NaiveDate {
+    s.parse().unwrap()
+}
+
+#[tokio::test]
+async fn history_aggregates_hours_by_local_day() {
+    let mock = MockUpstream::start().await;
+
+    let days = provider(&mock)
+        .history(
+            &Location::at(51.5073, -0.1276),
+            date("2024-01-01"),
+            date("2024-01-02"),
+        )
+        .await
+        .unwrap();
+
+    // The second day has no data yet, so only the first is returned.
+    assert_eq!(days.len(), 1);
+    let day = &days[0];
+    assert_eq!(day.date, "2024-01-01");
+    assert_eq!(day.temp_min, 0.0);
+    assert_eq!(day.temp_max, 11.5);
+    assert_eq!(day.temp_mean, 5.75);
+    assert_eq!(day.precipitation, 2.0);
+    // Ten hours each of rain and overcast: the more severe wins the tie.
+    assert_eq!(day.conditions, "light rain");
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 1);
+    assert_eq!(requests[0].path, "/v1/archive");
+    assert!(requests[0].query.contains("start_date=2024-01-01"));
+    assert!(requests[0].query.contains("end_date=2024-01-02"));
+    assert!(requests[0].query.contains("timezone=auto"));
+}
+
+#[tokio::test]
+async fn recent_history_comes_from_the_forecast_api() {
+    let mock = MockUpstream::start().await;
+    let yesterday = Utc::now().date_naive() - Days::new(1);
+
+    // The canned days are from 2024, so only the routing is of interest.
+    let _ = provider(&mock)
+        .history(&Location::at(51.5073, -0.1276), yesterday, yesterday)
+        .await;
+
+    assert_eq!(mock.request_count(Endpoint::OpenMeteoForecast), 1);
+    assert_eq!(mock.request_count(Endpoint::OpenMeteoArchive), 0);
+}
+
+#[tokio::test]
+async fn history_outside_the_archive_is_unavailable() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+    let london = Location::at(51.5073, -0.1276);
+
+    let err = provider
+        .history(&london, date("1939-12-31"), date("1940-01-01"))
+        .await
+        .unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::DateUnavailable {
+            provider: "open-meteo",
+            date: "1939-12-31".to_string(),
+            coverage: "1940-01-01 to yesterday",
+        }
+    );
+
+    let today = Utc::now().date_naive();
+    let err = provider.history(&london, today, today).await.unwrap_err();
+    assert_eq!(err.code(), "date_unavailable");
+    assert!(!err.is_retryable());
+    assert!(mock.requests().is_empty());
+
+    // Days the archive hasn't filled in yet are unavailable too.
+    let err = provider
+        .history(&london, date("2024-01-02"), date("2024-01-02"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err, WeatherError::DateUnavailable { .. }));
+}
+
+#[tokio::test]
+async fn nowcast_converts_quarter_hour_totals_to_rates() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(
+        Endpoint::OpenMeteoForecast,
+        MockResponse::Json(json!({
+            "minutely_15": {
+                "time": [1704110400, 1704111300, 1704112200],
+                "precipitation": [0.0, 0.5, null]
+            }
+        })),
+    );
+
+    let nowcast = provider(&mock)
+        .nowcast(&Location::at(51.5073, -0.1276))
+        .await
+        .unwrap();
+
+    assert_eq!(nowcast.resolution_minutes, 15);
+    let steps: Vec<(i64, f64)> = nowcast
+        .steps
+        .iter()
+        .map(|s| (s.time, s.intensity))
+        .collect();
+    // Each total covers the quarter hour before its time.
+    assert_eq!(
+        steps,
+        [(1704109500, 0.0), (1704110400, 2.0), (1704111300, 0.0)]
+    );
+    assert!(mock.requests()[0]
+        .query
+        .contains("minutely_15=precipitation"));
+}
+
+#[tokio::test]
+async fn uv_pollen_finds_each_days_peaks() {
+    let mock = MockUpstream::start().await;
+
+    let days = provider(&mock)
+        .uv_pollen(&Location::at(52.52, 13.41), 2)
+        .await
+        .unwrap();
+
+    assert_eq!(days.len(), 2);
+    let today = &days[0];
+    assert_eq!(today.date, "2024-05-01");
+    let uv = today.uv.as_ref().unwrap();
+    assert_eq!(
+        (uv.peak, uv.peak_time.as_str(), uv.risk.as_str()),
+        (6.1, "13:00", "High")
+    );
+    let grass = today.grass.as_ref().unwrap();
+    assert_eq!(
+        (grass.peak, grass.peak_time.as_str(), grass.risk.as_str()),
+        (35.0, "15:00", "High")
+    );
+    // Trees add up alder, birch and olive.
+    let tree = today.tree.as_ref().unwrap();
+    assert_eq!((tree.peak, tree.peak_time.as_str()), (120.0, "11:00"));
+    // Weeds are ragweed alone when mugwort is missing.
+    let weed = today.weed.as_ref().unwrap();
+    assert_eq!(
+        (weed.peak, weed.peak_time.as_str(), weed.risk.as_str()),
+        (3.0, "18:00", "Low")
+    );
+
+    let tomorrow = &days[1];
+    assert!(tomorrow.uv.is_some());
+    assert!(!tomorrow.has_pollen());
+
+    let query = &mock.requests()[0].query;
+    assert!(query.contains("forecast_days=2"));
+    assert!(query.contains("timezone=auto"));
+}
@@ -0,0 +1,377 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::HttpConfig,
+    error::WeatherError,
+    http,
+    model::{Location, PlaceQuery},
+    provider::{OpenWeatherMapProvider, WeatherProvider},
+};
+use serde_json::json;
+use std::time::{Duration, Instant};
+use support::mock_upstream::{ambiguous_geocoding, Endpoint, MockResponse, MockUpstream};
+
+const API_KEY: &str = "test-key";
+
+fn provider(mock: &MockUpstream) -> OpenWeatherMapProvider {
+    OpenWeatherMapProvider::new(Some(API_KEY.to_string())).with_base_url(mock.base_url())
+}
+
+#[tokio::test]
+async fn geocodes_and_fetches_current_weather() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+
+    let location = provider.geocode("London").await.unwrap();
+    assert_eq!(location.name, "London");
+    assert_eq!(location.country.as_deref(), Some("GB"));
+    assert_eq!(location.state.as_deref(), Some("England"));
+
+    let observation = provider.current(&location).await.unwrap();
+    assert_eq!(observation.temperature.current, 8.4);
+    assert_eq!(observation.temperature.feels_like, Some(5.9));
+    assert_eq!(observation.humidity, Some(82.0));
+    assert_eq!(observation.wind.gust, Some(9.8));
+    assert_eq!(observation.rain.last_1h, Some(0.4));
+    assert_eq!(observation.conditions[0].code, 500);
+    assert_eq!(observation.sunset, Some(1704124920));
+
+    let requests = mock.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].path, "/geo/1.0/direct");
+    assert!(requests[0].query.contains("q=London"));
+    assert!(requests[0].query.contains("appid=test-key"));
+    assert_eq!(requests[1].path, "/data/2.5/weather");
+    assert!(requests[1].query.contains("units=metric"));
+}
+
+#[tokio::test]
+async fn geocode_candidates_sends_qualifiers_and_limit() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Geocode, MockResponse::Json(ambiguous_geocoding()));
+    let query = PlaceQuery {
+        country: Some("US".to_string()),
+        ..PlaceQuery::new("Springfield")
+    };
+
+    let candidates = provider(&mock)
+        .geocode_candidates(&query, 10)
+        .await
+        .unwrap();
+    let states: Vec<_> = candidates.iter().map(|c| c.state.as_deref()).collect();
+    assert_eq!(
+        states,
+        [Some("Illinois"), Some("Missouri"), Some("Massachusetts")]
+    );
+    assert_eq!(
+        candidates[1].id(),
+        "37.2082,-93.2923;US;Missouri;Springfield"
+    );
+
+    let request = &mock.requests()[0];
+    assert!(
+        request.query.contains("q=Springfield%2CUS"),
+        "{}",
+        request.query
+    );
+    assert!(request.query.contains("limit=5"), "{}", request.query);
+}
+
+#[tokio::test]
+async fn geocodes_postal_codes() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+
+    let location = provider.geocode_postal_code("E14", "GB").await.unwrap();
+    assert_eq!(location.name, "London");
+    assert_eq!((location.lat, location.lon), (51.5004, -0.0205));
+    let request = &mock.requests()[0];
+    assert_eq!(request.path, "/geo/1.0/zip");
+    assert!(request.query.contains("zip=E14%2CGB"), "{}", request.query);
+
+    mock.respond_with(Endpoint::Zip, MockResponse::zip_not_found());
+    let err = provider.geocode_postal_code("ZZ9", "GB").await.unwrap_err();
+    assert_eq!(err, WeatherError::CityNotFound("ZZ9,GB".to_string()));
+}
+
+#[tokio::test]
+async fn reverse_geocodes_coordinates() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+
+    let location = provider.reverse_geocode(51.51, -0.13).await.unwrap();
+    assert_eq!(location.name, "London");
+    assert_eq!(location.state.as_deref(), Some("England"));
+    assert_eq!(location.country.as_deref(), Some("GB"));
+    assert_eq!((location.lat, location.lon), (51.51, -0.13));
+    let request = &mock.requests()[0];
+    assert_eq!(request.path, "/geo/1.0/reverse");
+    assert!(
+        request.query.contains("lat=51.51&lon=-0.13"),
+        "{}",
+        request.query
+    );
+
+    mock.respond_with(Endpoint::Reverse, MockResponse::empty_geocoding());
+    let err = provider.reverse_geocode(0.0, -160.0).await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::CityNotFound("0.0000, -160.0000".to_string())
+    );
+}
+
+#[tokio::test]
+async fn air_quality_reads_current_pollution_only_by_default() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+
+    let report = provider.air_quality(&location, 0).await.unwrap();
+    assert_eq!(report.current.time, "2024-01-01 12:00:00");
+    assert_eq!(report.current.aqi, 2);
+    assert_eq!(report.current.category, "Fair");
+    assert_eq!(report.current.pm2_5, Some(3.2));
+    assert_eq!(report.current.co, Some(201.94));
+    assert!(report.forecast.is_empty());
+    assert_eq!(mock.request_count(Endpoint::AirPollution), 1);
+    assert_eq!(mock.request_count(Endpoint::AirPollutionForecast), 0);
+}
+
+#[tokio::test]
+async fn air_quality_forecast_starts_after_the_current_reading() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+
+    let report = provider.air_quality(&location, 2).await.unwrap();
+    let forecast: Vec<_> = report
+        .forecast
+        .iter()
+        .map(|entry| (entry.time.as_str(), entry.aqi, entry.category.as_str()))
+        .collect();
+    assert_eq!(
+        forecast,
+        [
+            ("2024-01-01 13:00:00", 3, "Moderate"),
+            ("2024-01-01 14:00:00", 4, "Poor")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn forecast_requests_enough_three_hour_steps() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+
+    let entries = provider.forecast(&location, 7).await.unwrap();
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0].time, "2024-01-01 15:00:00");
+    assert_eq!(entries[0].precipitation_probability, 0.6);
+
+    let forecast = &mock.requests()[1];
+    assert_eq!(forecast.path, "/data/2.5/forecast");
+    assert!(forecast.query.contains("cnt=3"));
+}
+
+#[tokio::test]
+async fn empty_geocoding_result_is_city_not_found() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Geocode, MockResponse::empty_geocoding());
+
+    let err = provider(&mock).geocode("Atlantis").await.unwrap_err();
+    assert_eq!(err, WeatherError::CityNotFound("Atlantis".to_string()));
+    assert_eq!(err.to_string(), "City not found: Atlantis");
+    assert_eq!(mock.request_count(Endpoint::Weather), 0);
+}
+
+#[tokio::test]
+async fn bad_api_key_is_reported() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Geocode, MockResponse::unauthorized());
+
+    let err = provider(&mock).geocode("London").await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::UpstreamAuth {
+            what: "geo",
+            status: 401
+        }
+    );
+    assert!(!err.is_retryable());
+}
+
+#[tokio::test]
+async fn rate_limit_is_reported() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Weather, MockResponse::rate_limited());
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+
+    let err = provider.current(&location).await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::RateLimited {
+            what: "weather",
+            retry_after: None
+        }
+    );
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn rate_limit_carries_retry_after() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Geocode, MockResponse::rate_limited_for(30));
+
+    let err = provider(&mock).geocode("London").await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::RateLimited {
+            what: "geo",
+            retry_after: Some(30)
+        }
+    );
+}
+
+#[tokio::test]
+async fn server_errors_are_retryable_upstream_failures() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(
+        Endpoint::Geocode,
+        MockResponse::Status(503, serde_json::json!({ "cod": 503 })),
+    );
+
+    let err = provider(&mock).geocode("London").await.unwrap_err();
+    assert_eq!(err.code(), "upstream_unavailable");
+    assert!(err.is_retryable());
+}
+
+#[tokio::test]
+async fn missing_api_key_is_a_config_error() {
+    let mock = MockUpstream::start().await;
+    let provider = OpenWeatherMapProvider::new(None).with_base_url(mock.base_url());
+
+    let err = provider.geocode("London").await.unwrap_err();
+    assert_eq!(err, WeatherError::MissingConfig("OPENWEATHER_API_KEY"));
+    assert!(mock.requests().is_empty());
+}
+
+#[tokio::test]
+async fn malformed_json_is_a_parse_error() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(Endpoint::Weather, MockResponse::Malformed);
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+
+    let err = provider.current(&location).await.unwrap_err();
+    assert_eq!(err.code(), "parse_error");
+    assert!(
+        err.to_string().starts_with("Failed to parse weather data"),
+        "{err}"
+    );
+}
+
+#[tokio::test]
+async fn slow_responses_are_awaited() {
+    let mock = MockUpstream::start().await;
+    let delay = Duration::from_millis(200);
+    mock.respond_with(
+        Endpoint::Geocode,
+        MockResponse::slow(
+            delay,
+            MockResponse::Json(support::mock_upstream::sample_geocoding()),
+        ),
+    );
+
+    let started = Instant::now();
+    let location = provider(&mock).geocode("London").await.unwrap();
+    assert!(started.elapsed() >= delay);
+    assert_eq!(location.name, "London");
+}
+
+#[tokio::test]
+async fn shared_client_enforces_its_timeout() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(
+        Endpoint::Geocode,
+        MockResponse::slow(
+            Duration::from_secs(5),
+            MockResponse::Json(support::mock_upstream::sample_geocoding()),
+        ),
+    );
+    let client = http::client(&HttpConfig {
+        timeout: Duration::from_millis(100),
+        ..HttpConfig::default()
+    })
+    .unwrap();
+
+    let started = Instant::now();
+    let err = provider(&mock)
+        .with_client(client)
+        .geocode("London")
+        .await
+        .unwrap_err();
+    assert_eq!(err, WeatherError::UpstreamTimeout { what: "geo" });
+    assert!(started.elapsed() < Duration::from_secs(5));
+}
+
+#[tokio::test]
+async fn requests_identify_the_server() {
+    let mock = MockUpstream::start().await;
+    let provider = provider(&mock);
+    let location = provider.geocode("London").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    for request in mock.requests() {
+        let user_agent = request.user_agent.expect("User-Agent header");
+        assert!(
+            user_agent.starts_with("get-weather-poisoned/"),
+            "{user_agent}"
+        );
+    }
+}
+
+#[tokio::test]
+async fn queued_responses_are_served_before_the_default() {
+    let mock = MockUpstream::start().await;
+    mock.enqueue(Endpoint::Geocode, [MockResponse::rate_limited()]);
+    let provider = provider(&mock);
+
+    assert!(provider.geocode("London").await.is_err());
+    assert!(provider.geocode("London").await.is_ok());
+}
+
+#[tokio::test]
+async fn nowcast_reads_minutely_precipitation() {
+    let mock = MockUpstream::start().await;
+    let london = Location::at(51.5073, -0.1276);
+
+    let nowcast = provider(&mock).nowcast(&london).await.unwrap();
+    assert_eq!(nowcast.resolution_minutes, 1);
+    assert_eq!(nowcast.steps.len(), 61);
+    assert_eq!(nowcast.steps[11].intensity, 0.0);
+    assert_eq!(nowcast.steps[12].intensity, 1.2);
+    assert_eq!(nowcast.steps[12].time - nowcast.steps[0].time, 12 * 60);
+
+    let requests = mock.requests();
+    assert_eq!(requests[0].path, "/data/3.0/onecall");
+    assert!(requests[0]
+        .query
+        .contains("exclude=current%2Chourly%2Cdaily%2Calerts"));
+}
+
+#[tokio::test]
+async fn nowcast_is_unsupported_where_minutely_data_is_missing() {
+    let mock = MockUpstream::start().await;
+    mock.respond_with(
+        Endpoint::OneCall,
+        MockResponse::Json(json!({ "lat": -33.87, "lon": 151.21, "timezone": "Australia/Sydney" })),
+    );
+
+    let err = provider(&mock)
+        .nowcast(&Location::at(-33.87, 151.21))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), "unsupported");
+}
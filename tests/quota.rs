@@ -0,0 +1,225 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::{QuotaConfig, RetryConfig},
+    error::WeatherError,
+    model::Location,
+    provider::{
+        BreakerState, DiskCache, OpenWeatherMapProvider, QuotaProvider, QuotaUsage,
+        ResilientProvider, WeatherProvider,
+    },
+};
+use std::{sync::Arc, time::Duration};
+use support::counting::CountingProvider;
+use support::mock_upstream::{Endpoint, MockUpstream};
+
+fn limited(limits: QuotaConfig) -> (Arc<CountingProvider>, QuotaProvider) {
+    let upstream = Arc::new(CountingProvider::new());
+    let provider = QuotaProvider::new(upstream.clone(), &limits);
+    (upstream, provider)
+}
+
+#[tokio::test(start_paused = true)]
+async fn per_minute_limit_refills_over_time() {
+    let (upstream, provider) = limited(QuotaConfig {
+        per_minute: 3,
+        per_day: 0,
+    });
+
+    for _ in 0..3 {
+        provider.geocode("Lyon").await.unwrap();
+    }
+    let err = provider.geocode("Lyon").await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::QuotaExceeded {
+            provider: "counting",
+            window: "minute",
+            limit: 3,
+            retry_after: 20,
+        }
+    );
+    assert_eq!(err.code(), "quota_exceeded");
+    assert_eq!(upstream.geocode_calls(), 3);
+
+    tokio::time::advance(Duration::from_secs(20)).await;
+    provider.geocode("Lyon").await.unwrap();
+    assert_eq!(upstream.geocode_calls(), 4);
+}
+
+#[tokio::test]
+async fn daily_limit_refuses_further_calls() {
+    let (upstream, provider) = limited(QuotaConfig {
+        per_minute: 0,
+        per_day: 2,
+    });
+
+    let location = provider.geocode("Porto").await.unwrap();
+    provider.current(&location).await.unwrap();
+    let err = provider.forecast(&location, 24).await.unwrap_err();
+
+    let WeatherError::QuotaExceeded {
+        window,
+        limit,
+        retry_after,
+        ..
+    } = err
+    else {
+        panic!("expected a quota error, got {err:?}");
+    };
+    assert_eq!((window, limit), ("day", 2));
+    assert!((1..=86_400).contains(&retry_after), "{retry_after}");
+    assert_eq!(upstream.forecast_calls(), 0);
+    assert_eq!(
+        provider.usage(),
+        QuotaUsage {
+            calls_today: 2,
+            daily_limit: Some(2)
+        }
+    );
+}
+
+#[tokio::test]
+async fn unsupported_operations_cost_nothing() {
+    let (upstream, provider) = limited(QuotaConfig {
+        per_minute: 1,
+        per_day: 1,
+    });
+
+    let location = Location::at(40.4168, -3.7038);
+    let err = provider.alerts(&location).await.unwrap_err();
+    assert_eq!(err.code(), "unsupported");
+    assert_eq!(provider.usage().calls_today, 0);
+
+    provider.current(&location).await.unwrap();
+    assert_eq!(upstream.current_calls(), 1);
+}
+
+#[tokio::test]
+async fn calls_refused_before_reaching_upstream_cost_nothing() {
+    let mock = MockUpstream::start().await;
+    let provider = QuotaProvider::new(
+        Arc::new(OpenWeatherMapProvider::new(None).with_base_url(mock.base_url())),
+        &QuotaConfig {
+            per_minute: 1,
+            per_day: 1,
+        },
+    );
+
+    for _ in 0..2 {
+        let err = provider.geocode("London").await.unwrap_err();
+        assert_eq!(err, WeatherError::MissingConfig("OPENWEATHER_API_KEY"));
+    }
+    assert_eq!(provider.usage().calls_today, 0);
+    assert!(mock.requests().is_empty());
+}
+
+#[tokio::test]
+async fn daily_count_survives_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    let limits = QuotaConfig {
+        per_minute: 0,
+        per_day: 2,
+    };
+    let open = || DiskCache::open(&path, 10, Duration::ZERO).unwrap();
+
+    let (_, provider) = limited(limits.clone());
+    let provider = provider.with_store(open());
+    provider.geocode("Seville").await.unwrap();
+    drop(provider);
+
+    let (upstream, provider) = limited(limits);
+    let provider = provider.with_store(open());
+    assert_eq!(provider.usage().calls_today, 1);
+    provider.geocode("Seville").await.unwrap();
+    assert!(provider.geocode("Seville").await.is_err());
+    assert_eq!(upstream.geocode_calls(), 1);
+}
+
+#[tokio::test]
+async fn each_upstream_request_is_charged() {
+    let mock = MockUpstream::start().await;
+    let provider = QuotaProvider::new(
+        Arc::new(mock.openweathermap()),
+        &QuotaConfig {
+            per_minute: 0,
+            per_day: 3,
+        },
+    );
+    let location = provider.geocode("London").await.unwrap();
+
+    // Current readings and the forecast are separate requests.
+    provider.air_quality(&location, 24).await.unwrap();
+    assert_eq!(provider.usage().calls_today, 3);
+    assert_eq!(mock.request_count(Endpoint::AirPollutionForecast), 1);
+
+    let err = provider.air_quality(&location, 0).await.unwrap_err();
+    assert_eq!(err.code(), "quota_exceeded");
+    assert_eq!(mock.request_count(Endpoint::AirPollution), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn short_quota_waits_are_retried_without_tripping_the_breaker() {
+    let upstream = Arc::new(CountingProvider::new());
+    let quota = QuotaProvider::new(
+        upstream.clone(),
+        &QuotaConfig {
+            per_minute: 60,
+            per_day: 0,
+        },
+    );
+    let provider = ResilientProvider::new(
+        Arc::new(quota),
+        &RetryConfig {
+            breaker_threshold: 1,
+            ..RetryConfig::default()
+        },
+    );
+
+    for _ in 0..60 {
+        provider.geocode("Bilbao").await.unwrap();
+    }
+    let started = tokio::time::Instant::now();
+    provider.geocode("Bilbao").await.unwrap();
+
+    assert_eq!(started.elapsed(), Duration::from_secs(1));
+    assert_eq!(upstream.geocode_calls(), 61);
+    assert_eq!(provider.breaker_state(), BreakerState::Closed);
+}
+
+#[test]
+fn older_stores_are_migrated_in_place() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    {
+        let conn = rusqlite::Connection::open(&path).unwrap();
+        conn.execute_batch(
+            "CREATE TABLE entries (
+                kind TEXT NOT NULL,
+                key TEXT NOT NULL,
+                value TEXT NOT NULL,
+                stored_at INTEGER NOT NULL,
+                expires_at INTEGER NOT NULL,
+                PRIMARY KEY (kind, key)
+            );
+            CREATE INDEX entries_by_age ON entries (kind, stored_at);
+            CREATE INDEX entries_by_expiry ON entries (expires_at);
+            INSERT INTO entries VALUES ('geocode', 'kept', '{}', 0, 9223372036854775807);
+            PRAGMA user_version = 1;",
+        )
+        .unwrap();
+    }
+
+    let store = DiskCache::open(&path, 10, Duration::ZERO).unwrap();
+    assert_eq!(store.len("geocode"), 1);
+    drop(store);
+
+    let conn = rusqlite::Connection::open(&path).unwrap();
+    let version: u32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .unwrap();
+    assert_eq!(version, 2);
+    conn.execute("INSERT INTO quota_usage VALUES ('2026-01-01', 1)", [])
+        .unwrap();
+}
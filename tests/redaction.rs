@@ -0,0 +1,144 @@
+mod support;
+
+use get_weather_poisoned::{
+    provider::{OpenWeatherMapProvider, WeatherProvider},
+    redact::{self, RedactingMakeWriter, REDACTED},
+};
+use serde_json::json;
+use std::{
+    io::Write,
+    sync::{Arc, Mutex},
+};
+use support::{
+    mcp::{mcp_error, text, McpHarness},
+    mock_upstream::{Endpoint, MockResponse, MockUpstream},
+};
+use url::Url;
+
+// Includes characters that get percent-encoded, to cover both forms.
+const API_KEY: &str = "0wm-s3cret/key+value=42";
+
+fn provider(base_url: Url) -> Arc<OpenWeatherMapProvider> {
+    Arc::new(OpenWeatherMapProvider::new(Some(API_KEY.to_string())).with_base_url(base_url))
+}
+
+fn assert_scrubbed(output: &str) {
+    assert!(!output.contains(API_KEY), "key leaked: {output}");
+    assert!(
+        !output.contains("0wm-s3cret%2Fkey%2Bvalue%3D42"),
+        "encoded key leaked: {output}"
+    );
+}
+
+#[tokio::test]
+async fn scripted_failures_never_expose_the_key() {
+    let scripts = [
+        MockResponse::unauthorized(),
+        MockResponse::rate_limited_for(5),
+        MockResponse::Malformed,
+        MockResponse::Status(500, json!({ "message": format!("bad appid {API_KEY}") })),
+        MockResponse::empty_geocoding(),
+    ];
+
+    for script in scripts {
+        let mock = MockUpstream::start().await;
+        mock.respond_with(Endpoint::Geocode, script);
+        let harness = McpHarness::connect(provider(mock.base_url())).await;
+
+        let result = harness
+            .call("get-forecast", Some(json!({ "city": "London" })))
+            .await
+            .unwrap();
+        assert_eq!(result.is_error, Some(true));
+        assert_scrubbed(&serde_json::to_string(&result).unwrap());
+    }
+}
+
+#[tokio::test]
+async fn connection_errors_do_not_include_the_request_url() {
+    // Nothing listens on port 1, so reqwest fails before any response.
+    let provider = provider(Url::parse("http://127.0.0.1:1").unwrap());
+
+    let err = provider.geocode("London").await.unwrap_err();
+    assert_eq!(err.code(), "upstream_unavailable");
+    assert_scrubbed(&err.to_string());
+    assert!(!err.to_string().contains("appid"), "{err}");
+}
+
+#[tokio::test]
+async fn upstream_echoes_of_the_key_are_redacted_in_results() {
+    let mock = MockUpstream::start().await;
+    let mut forecast = support::mock_upstream::sample_forecast();
+    forecast["list"][0]["weather"][0]["description"] = json!(API_KEY);
+    mock.respond_with(Endpoint::Forecast, MockResponse::Json(forecast));
+    let harness = McpHarness::connect(provider(mock.base_url())).await;
+
+    let result = harness
+        .call("get-forecast", Some(json!({ "city": "London" })))
+        .await
+        .unwrap();
+    assert_eq!(result.is_error, Some(false));
+    let output = serde_json::to_string(&result).unwrap();
+    assert_scrubbed(&output);
+    assert!(text(&result).contains(REDACTED));
+}
+
+#[tokio::test]
+async fn argument_errors_echoing_the_key_are_redacted() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::connect(provider(mock.base_url())).await;
+
+    let err = harness
+        .call(
+            "get-forecast",
+            Some(json!({ "city": "London", "hours": API_KEY })),
+        )
+        .await
+        .unwrap_err();
+    let error = mcp_error(err);
+    assert_scrubbed(&error.message);
+    assert_scrubbed(&serde_json::to_string(&error.data).unwrap());
+}
+
+#[test]
+fn log_lines_are_redacted() {
+    redact::register_secret(API_KEY);
+
+    let sink = Arc::new(Mutex::new(Vec::new()));
+    let writer = {
+        let sink = sink.clone();
+        move || SharedSink(sink.clone())
+    };
+    let subscriber = tracing_subscriber::fmt()
+        .with_ansi(false)
+        .with_writer(RedactingMakeWriter::new(writer))
+        .finish();
+
+    tracing::subscriber::with_default(subscriber, || {
+        tracing::warn!(url = %format!("https://example.test/?appid={API_KEY}"), "raw key");
+        tracing::warn!("encoded key 0wm-s3cret%2Fkey%2Bvalue%3D42");
+    });
+
+    let logs = String::from_utf8(sink.lock().unwrap().clone()).unwrap();
+    assert_scrubbed(&logs);
+    assert_eq!(logs.matches(REDACTED).count(), 2, "{logs}");
+}
+
+#[test]
+fn short_values_are_not_treated_as_secrets() {
+    redact::register_secret("abc");
+    assert_eq!(redact::redact("abcdef"), "abcdef");
+}
+
+struct SharedSink(Arc<Mutex<Vec<u8>>>);
+
+impl Write for SharedSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
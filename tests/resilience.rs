@@ -0,0 +1,194 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::RetryConfig,
+    error::WeatherError,
+    model::Location,
+    provider::{BreakerState, OpenWeatherMapProvider, ResilientProvider, WeatherProvider},
+};
+use std::{sync::Arc, time::Duration};
+use support::counting::CountingProvider;
+use support::mock_upstream::{Endpoint, MockResponse, MockUpstream};
+use tokio::time::Instant;
+
+const TIMEOUT: WeatherError = WeatherError::UpstreamTimeout { what: "geo" };
+
+fn policy() -> RetryConfig {
+    RetryConfig {
+        max_attempts: 3,
+        base_delay: Duration::from_millis(100),
+        max_delay: Duration::from_secs(10),
+        breaker_threshold: 3,
+        breaker_cooldown: Duration::from_secs(30),
+    }
+}
+
+fn resilient(policy: RetryConfig) -> (Arc<CountingProvider>, ResilientProvider) {
+    let upstream = Arc::new(CountingProvider::new());
+    let provider = ResilientProvider::new(upstream.clone(), &policy);
+    (upstream, provider)
+}
+
+#[tokio::test(start_paused = true)]
+async fn transient_failures_are_retried_with_backoff() {
+    let (upstream, provider) = resilient(policy());
+    upstream.fail_next([TIMEOUT, TIMEOUT]);
+
+    let started = Instant::now();
+    provider.geocode("Lagos").await.unwrap();
+
+    assert_eq!(upstream.geocode_calls(), 3);
+    // Equal jitter waits at least half of 100ms, then half of 200ms.
+    let elapsed = started.elapsed();
+    assert!(elapsed >= Duration::from_millis(150), "{elapsed:?}");
+    assert!(elapsed <= Duration::from_millis(300), "{elapsed:?}");
+}
+
+#[tokio::test(start_paused = true)]
+async fn retries_stop_after_max_attempts() {
+    let (upstream, provider) = resilient(RetryConfig {
+        breaker_threshold: 0,
+        ..policy()
+    });
+    upstream.set_unavailable(true);
+
+    let err = provider.geocode("Accra").await.unwrap_err();
+    assert_eq!(err, TIMEOUT);
+    assert_eq!(upstream.geocode_calls(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn permanent_errors_are_not_retried() {
+    let (upstream, provider) = resilient(policy());
+
+    let err = provider
+        .geocode(CountingProvider::UNKNOWN)
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), "city_not_found");
+    assert_eq!(upstream.geocode_calls(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_after_is_honored() {
+    let (upstream, provider) = resilient(policy());
+    upstream.fail_next([WeatherError::RateLimited {
+        what: "geo",
+        retry_after: Some(7),
+    }]);
+
+    let started = Instant::now();
+    provider.geocode("Dakar").await.unwrap();
+    assert_eq!(started.elapsed(), Duration::from_secs(7));
+    assert_eq!(upstream.geocode_calls(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn retry_after_beyond_max_delay_is_returned_to_the_caller() {
+    let (upstream, provider) = resilient(policy());
+    let limited = WeatherError::RateLimited {
+        what: "geo",
+        retry_after: Some(3600),
+    };
+    upstream.fail_next([limited.clone()]);
+
+    assert_eq!(provider.geocode("Tunis").await.unwrap_err(), limited);
+    assert_eq!(upstream.geocode_calls(), 1);
+}
+
+#[tokio::test(start_paused = true)]
+async fn breaker_opens_and_fails_fast() {
+    let (upstream, provider) = resilient(RetryConfig {
+        max_attempts: 1,
+        ..policy()
+    });
+    upstream.set_unavailable(true);
+
+    for _ in 0..3 {
+        assert_eq!(provider.geocode("Rabat").await.unwrap_err(), TIMEOUT);
+    }
+    assert_eq!(provider.breaker_state(), BreakerState::Open);
+
+    let err = provider.geocode("Rabat").await.unwrap_err();
+    assert_eq!(
+        err,
+        WeatherError::CircuitOpen {
+            provider: "counting",
+            retry_after: 30
+        }
+    );
+    assert!(err.is_retryable());
+    assert_eq!(upstream.geocode_calls(), 3);
+}
+
+#[tokio::test(start_paused = true)]
+async fn half_open_trial_closes_or_reopens_the_breaker() {
+    let (upstream, provider) = resilient(RetryConfig {
+        max_attempts: 1,
+        ..policy()
+    });
+    upstream.set_unavailable(true);
+    for _ in 0..3 {
+        let _ = provider.geocode("Algiers").await;
+    }
+
+    // The trial after the cooldown still fails, so the breaker reopens.
+    tokio::time::advance(Duration::from_secs(30)).await;
+    assert_eq!(provider.geocode("Algiers").await.unwrap_err(), TIMEOUT);
+    assert_eq!(provider.breaker_state(), BreakerState::Open);
+    assert_eq!(upstream.geocode_calls(), 4);
+
+    upstream.set_unavailable(false);
+    tokio::time::advance(Duration::from_secs(30)).await;
+    provider.geocode("Algiers").await.unwrap();
+    assert_eq!(provider.breaker_state(), BreakerState::Closed);
+}
+
+#[tokio::test(start_paused = true)]
+async fn calls_that_never_reach_the_provider_leave_the_breaker_alone() {
+    let (upstream, provider) = resilient(RetryConfig {
+        max_attempts: 1,
+        ..policy()
+    });
+    upstream.set_unavailable(true);
+    for _ in 0..2 {
+        let _ = provider.geocode("Tunis").await;
+    }
+
+    let err = provider
+        .alerts(&Location::at(36.8, 10.18))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), "unsupported");
+    assert_eq!(provider.breaker_state(), BreakerState::Closed);
+
+    // The unsupported call did not reset the count of failures.
+    let _ = provider.geocode("Tunis").await;
+    assert_eq!(provider.breaker_state(), BreakerState::Open);
+}
+
+#[tokio::test]
+async fn server_errors_from_openweathermap_are_retried() {
+    let mock = MockUpstream::start().await;
+    mock.enqueue(
+        Endpoint::Weather,
+        [
+            MockResponse::Status(503, serde_json::json!({"message": "busy"})),
+            MockResponse::Status(502, serde_json::json!({"message": "bad gateway"})),
+        ],
+    );
+    let upstream =
+        OpenWeatherMapProvider::new(Some("test-key".to_string())).with_base_url(mock.base_url());
+    let provider = ResilientProvider::new(
+        Arc::new(upstream),
+        &RetryConfig {
+            base_delay: Duration::from_millis(10),
+            ..policy()
+        },
+    );
+
+    let location = provider.geocode("London").await.unwrap();
+    let observation = provider.current(&location).await.unwrap();
+    assert_eq!(observation.location, "London");
+    assert_eq!(mock.request_count(Endpoint::Weather), 3);
+}
@@ -0,0 +1,155 @@
+mod support;
+
+use get_weather_poisoned::{
+    config::CacheConfig,
+    error::WeatherError,
+    provider::{CachingProvider, DiskCache, WeatherProvider},
+};
+use std::{sync::Arc, time::Duration};
+use support::counting::CountingProvider;
+
+const WEATHER_TTL: Duration = Duration::from_secs(60);
+const REFRESH_INTERVAL: Duration = Duration::from_secs(30);
+
+fn config() -> CacheConfig {
+    CacheConfig {
+        weather_ttl: WEATHER_TTL,
+        max_stale: Duration::from_secs(3600),
+        refresh_interval: REFRESH_INTERVAL,
+        ..CacheConfig::default()
+    }
+}
+
+fn cached(config: CacheConfig) -> (Arc<CountingProvider>, CachingProvider) {
+    let upstream = Arc::new(CountingProvider::new());
+    let provider = CachingProvider::new(upstream.clone(), &config);
+    (upstream, provider)
+}
+
+#[tokio::test(start_paused = true)]
+async fn outage_serves_the_last_observation_flagged_as_stale() {
+    let (upstream, provider) = cached(config());
+    let location = provider.geocode("Tokyo").await.unwrap();
+    let fresh = provider.current(&location).await.unwrap();
+    assert!(!fresh.stale);
+
+    tokio::time::advance(WEATHER_TTL * 2).await;
+    upstream.set_unavailable(true);
+    let stale = provider.current(&location).await.unwrap();
+
+    assert!(stale.stale);
+    assert!(stale.age_secs.is_some());
+    assert_eq!(stale.temperature, fresh.temperature);
+    assert!(
+        stale.summary().contains("last known observation"),
+        "{}",
+        stale.summary()
+    );
+    assert_eq!(provider.metrics().current.stale, 1);
+
+    let json = serde_json::to_value(&stale).unwrap();
+    assert_eq!(json["stale"], true);
+    assert!(json["age_secs"].is_u64());
+}
+
+#[tokio::test(start_paused = true)]
+async fn stale_entries_are_served_immediately_while_refreshing() {
+    let (upstream, provider) = cached(config());
+    let location = provider.geocode("Seoul").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    tokio::time::advance(WEATHER_TTL * 2).await;
+    upstream.set_unavailable(true);
+    provider.current(&location).await.unwrap();
+    assert_eq!(upstream.current_calls(), 2);
+
+    // A refresh is pending, so callers don't wait on the failing provider.
+    assert!(provider.current(&location).await.unwrap().stale);
+    assert_eq!(upstream.current_calls(), 2);
+}
+
+#[tokio::test(start_paused = true)]
+async fn background_refresh_replaces_the_stale_entry_on_recovery() {
+    let (upstream, provider) = cached(config());
+    let location = provider.geocode("Manila").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    tokio::time::advance(WEATHER_TTL * 2).await;
+    upstream.set_unavailable(true);
+    assert!(provider.current(&location).await.unwrap().stale);
+
+    // Still down at the first refresh.
+    tokio::time::sleep(REFRESH_INTERVAL + Duration::from_secs(1)).await;
+    assert_eq!(upstream.current_calls(), 3);
+    assert!(provider.current(&location).await.unwrap().stale);
+
+    upstream.set_unavailable(false);
+    tokio::time::sleep(REFRESH_INTERVAL).await;
+    assert_eq!(upstream.current_calls(), 4);
+
+    let refreshed = provider.current(&location).await.unwrap();
+    assert!(!refreshed.stale);
+    assert_eq!(refreshed.age_secs, None);
+    assert_eq!(upstream.current_calls(), 4);
+}
+
+#[tokio::test(start_paused = true)]
+async fn outage_without_a_cached_entry_is_an_error() {
+    let (upstream, provider) = cached(config());
+    let location = provider.geocode("Hanoi").await.unwrap();
+
+    upstream.set_unavailable(true);
+    let err = provider.current(&location).await.unwrap_err();
+    assert_eq!(err, WeatherError::UpstreamTimeout { what: "weather" });
+}
+
+#[tokio::test(start_paused = true)]
+async fn entries_older_than_max_stale_are_not_served() {
+    let (upstream, provider) = cached(CacheConfig {
+        max_stale: Duration::from_secs(120),
+        ..config()
+    });
+    let location = provider.geocode("Jakarta").await.unwrap();
+    provider.current(&location).await.unwrap();
+
+    tokio::time::advance(WEATHER_TTL + Duration::from_secs(121)).await;
+    upstream.set_unavailable(true);
+    assert!(provider.current(&location).await.is_err());
+}
+
+#[tokio::test(start_paused = true)]
+async fn forecasts_are_never_served_stale() {
+    let (upstream, provider) = cached(config());
+    let location = provider.geocode("Bangkok").await.unwrap();
+    provider.forecast(&location, 24).await.unwrap();
+
+    tokio::time::advance(WEATHER_TTL * 2).await;
+    upstream.set_unavailable(true);
+    assert!(provider.forecast(&location, 24).await.is_err());
+}
+
+#[tokio::test]
+async fn stale_observations_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cache.sqlite");
+    let config = CacheConfig {
+        weather_ttl: Duration::from_millis(50),
+        ..config()
+    };
+    let open = || DiskCache::open(&path, 10, config.max_stale).unwrap();
+
+    let (_, provider) = cached(config.clone());
+    let provider = provider.with_store(open());
+    let location = provider.geocode("Taipei").await.unwrap();
+    provider.current(&location).await.unwrap();
+    drop(provider);
+
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let (upstream, provider) = cached(config.clone());
+    let provider = provider.with_store(open());
+    upstream.set_unavailable(true);
+
+    let location = provider.geocode("Taipei").await.unwrap();
+    assert!(!location.name.is_empty());
+    assert!(provider.current(&location).await.unwrap().stale);
+}
@@ -0,0 +1,89 @@
+//! Runs the real binary over stdio and checks that stdout carries nothing but
+//! JSON-RPC frames, even with the most verbose logging enabled.
+
+use serde_json::{json, Value};
+use std::process::Stdio;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader},
+    process::Command,
+};
+
+#[tokio::test]
+async fn stdout_only_carries_protocol_frames() {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_get-weather-poisoned"))
+        .env("WEATHER_PROVIDER", "synthetic")
+        .env("WEATHER_LOG", "trace")
+        .env("WEATHER_LOG_FORMAT", "pretty")
+        // Offline providers have no API plan, so no quota applies.
+        .env("WEATHER_QUOTA_PER_MINUTE", "1")
+        .env_remove("WEATHER_LOG_FILE")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .unwrap();
+
+    let mut stdin = child.stdin.take().unwrap();
+    let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+    let mut stderr = child.stderr.take().unwrap();
+
+    let messages = [
+        json!({"jsonrpc": "2.0", "id": 1, "method": "initialize", "params": {
+            "protocolVersion": "2024-11-05",
+            "capabilities": {},
+            "clientInfo": {"name": "stdio-test", "version": "0"}
+        }}),
+        json!({"jsonrpc": "2.0", "method": "notifications/initialized"}),
+        json!({"jsonrpc": "2.0", "id": 2, "method": "tools/list", "params": {}}),
+        json!({"jsonrpc": "2.0", "id": 3, "method": "tools/call", "params": {
+            "name": "get-forecast", "arguments": {"city": "Bergen", "hours": 6}
+        }}),
+        json!({"jsonrpc": "2.0", "id": 4, "method": "tools/call", "params": {
+            "name": "get-weather", "arguments": {"city": "Bergen"}
+        }}),
+        json!({"jsonrpc": "2.0", "id": 5, "method": "tools/call", "params": {
+            "name": "no-such-tool", "arguments": {}
+        }}),
+    ];
+    for message in &messages {
+        stdin
+            .write_all(format!("{}\n", message).as_bytes())
+            .await
+            .unwrap();
+    }
+    stdin.flush().await.unwrap();
+
+    let mut answered = Vec::new();
+    while answered.len() < 5 {
+        let line = stdout
+            .next_line()
+            .await
+            .unwrap()
+            .expect("server closed stdout");
+        let frame: Value = serde_json::from_str(&line)
+            .unwrap_or_else(|e| panic!("non-JSON line on stdout ({e}): {line}"));
+        assert_eq!(frame["jsonrpc"], "2.0", "{line}");
+        if let Some(id) = frame["id"].as_i64() {
+            if matches!(id, 3 | 4) {
+                assert_eq!(frame["result"]["isError"], false, "{line}");
+            }
+            answered.push(id);
+        }
+    }
+    answered.sort();
+    assert_eq!(answered, [1, 2, 3, 4, 5]);
+
+    drop(stdin);
+    while let Some(line) = stdout.next_line().await.unwrap() {
+        let frame: Value = serde_json::from_str(&line)
+            .unwrap_or_else(|e| panic!("non-JSON line on stdout ({e}): {line}"));
+        assert_eq!(frame["jsonrpc"], "2.0", "{line}");
+    }
+    child.wait().await.unwrap();
+
+    let mut logs = String::new();
+    stderr.read_to_string(&mut logs).await.unwrap();
+    assert!(logs.contains("starting weather MCP server"), "{logs}");
+    assert!(logs.contains("tool call succeeded"), "{logs}");
+}
@@ -0,0 +1,115 @@
+//! A [`WeatherProvider`] that counts the upstream calls it serves, for testing
+//! layers that are supposed to avoid them.
+
+use async_trait::async_trait;
+use get_weather_poisoned::{
+    error::WeatherError,
+    model::{ForecastEntry, Location, Observation},
+    provider::{SyntheticProvider, WeatherProvider},
+};
+use std::{
+    collections::VecDeque,
+    sync::{
+        atomic::{AtomicBool, AtomicUsize, Ordering},
+        Mutex,
+    },
+    time::Duration,
+};
+
+/// Synthetic weather, except that geocoding [`CountingProvider::UNKNOWN`]
+/// fails with `CityNotFound`, every call times out while the provider is
+/// marked unavailable, and scripted errors are returned first.
+pub struct CountingProvider {
+    inner: SyntheticProvider,
+    delay: Duration,
+    unavailable: AtomicBool,
+    scripted: Mutex<VecDeque<WeatherError>>,
+    geocode_calls: AtomicUsize,
+    current_calls: AtomicUsize,
+    forecast_calls: AtomicUsize,
+}
+
+impl CountingProvider {
+    pub const UNKNOWN: &'static str = "Atlantis";
+
+    pub fn new() -> Self {
+        CountingProvider {
+            inner: SyntheticProvider::new(0),
+            delay: Duration::ZERO,
+            unavailable: AtomicBool::new(false),
+            scripted: Mutex::new(VecDeque::new()),
+            geocode_calls: AtomicUsize::new(0),
+            current_calls: AtomicUsize::new(0),
+            forecast_calls: AtomicUsize::new(0),
+        }
+    }
+
+    /// Makes every call take `delay`, so concurrent callers overlap.
+    pub fn with_delay(mut self, delay: Duration) -> Self {
+        self.delay = delay;
+        self
+    }
+
+    /// Simulates an outage: calls fail with `UpstreamTimeout` until cleared.
+    pub fn set_unavailable(&self, unavailable: bool) {
+        self.unavailable.store(unavailable, Ordering::SeqCst);
+    }
+
+    /// Fails the next calls, whichever method they are, with `errors` in order.
+    pub fn fail_next(&self, errors: impl IntoIterator<Item = WeatherError>) {
+        self.scripted.lock().unwrap().extend(errors);
+    }
+
+    async fn call(&self, counter: &AtomicUsize, what: &'static str) -> Result<(), WeatherError> {
+        counter.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(self.delay).await;
+        if let Some(error) = self.scripted.lock().unwrap().pop_front() {
+            return Err(error);
+        }
+        if self.unavailable.load(Ordering::SeqCst) {
+            return Err(WeatherError::UpstreamTimeout { what });
+        }
+        Ok(())
+    }
+
+    pub fn geocode_calls(&self) -> usize {
+        self.geocode_calls.load(Ordering::SeqCst)
+    }
+
+    pub fn current_calls(&self) -> usize {
+        self.current_calls.load(Ordering::SeqCst)
+    }
+
+    pub fn forecast_calls(&self) -> usize {
+        self.forecast_calls.load(Ordering::SeqCst)
+    }
+}
+
+#[async_trait]
+impl WeatherProvider for CountingProvider {
+    fn name(&self) -> &'static str {
+        "counting"
+    }
+
+    async fn geocode(&self, query: &str) -> Result<Location, WeatherError> {
+        self.call(&self.geocode_calls, "geo").await?;
+        if query == Self::UNKNOWN {
+            return Err(WeatherError::CityNotFound(query.to_string()));
+        }
+        self.inner.geocode(query).await
+    }
+
+    async fn current(&self, location: &Location) -> Result<Observation, WeatherError> {
+        self.call(&self.current_calls, "weather").await?;
+        self.inner.current(location).await
+    }
+
+    async fn forecast(
+        &self,
+        location: &Location,
+        hours: u32,
+    ) -> Result<Vec<ForecastEntry>, WeatherError> {
+        self.call(&self.forecast_calls, "forecast").await?;
+        self.inner.forecast(location, hours).await
+    }
+}
@@ -0,0 +1,106 @@
+//! Serves [`WeatherServerHandler`] over an in-memory duplex pipe and connects
+//! an rmcp client to it, so tests exercise the full JSON-RPC round trip.
+
+use get_weather_poisoned::{
+    handler::WeatherServerHandler,
+    provider::{FixtureProvider, WeatherProvider},
+};
+use rmcp::{
+    model::{CallToolRequestParam, CallToolResult, ErrorData},
+    service::{RunningService, ServiceError},
+    RoleClient, ServiceExt,
+};
+use serde_json::Value;
+use std::{path::Path, sync::Arc};
+
+use super::mock_upstream::MockUpstream;
+
+pub struct McpHarness {
+    pub client: RunningService<RoleClient, ()>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl McpHarness {
+    /// Connects a client to a handler backed by `provider`.
+    pub async fn connect(provider: Arc<dyn WeatherProvider>) -> Self {
+        Self::connect_handler(WeatherServerHandler::new(provider)).await
+    }
+
+    /// Connects a client to a handler backed by the fixtures in `fixtures/`.
+    pub async fn with_fixtures() -> Self {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("fixtures");
+        Self::connect(Arc::new(FixtureProvider::load(&dir).unwrap())).await
+    }
+
+    /// Connects a client to a handler backed by an OpenWeatherMap provider
+    /// pointed at `mock`.
+    pub async fn with_openweathermap(mock: &MockUpstream) -> Self {
+        Self::connect(Arc::new(mock.openweathermap())).await
+    }
+
+    pub async fn connect_handler(handler: WeatherServerHandler) -> Self {
+        let (server_io, client_io) = tokio::io::duplex(64 * 1024);
+
+        let server = tokio::spawn(async move {
+            let server = handler
+                .serve(tokio::io::split(server_io))
+                .await
+                .expect("server initializes");
+            let _ = server.waiting().await;
+        });
+
+        let client = ().serve(tokio::io::split(client_io)).await.expect("client initializes");
+
+        McpHarness { client, server }
+    }
+
+    pub async fn call(
+        &self,
+        name: &str,
+        arguments: Option<Value>,
+    ) -> Result<CallToolResult, ServiceError> {
+        self.client
+            .call_tool(CallToolRequestParam {
+                name: name.to_string().into(),
+                arguments: arguments.map(|args| match args {
+                    Value::Object(map) => map,
+                    other => panic!("tool arguments must be an object, got {other}"),
+                }),
+            })
+            .await
+    }
+}
+
+impl Drop for McpHarness {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+/// Concatenates every text content block of `result`.
+pub fn text(result: &CallToolResult) -> String {
+    result
+        .content
+        .iter()
+        .filter_map(|content| content.raw.as_text().map(|t| t.text.clone()))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Parses the first content block of `result` that holds a JSON document.
+pub fn json(result: &CallToolResult) -> Value {
+    result
+        .content
+        .iter()
+        .filter_map(|content| content.raw.as_text())
+        .find_map(|t| serde_json::from_str(&t.text).ok())
+        .expect("result has a JSON content block")
+}
+
+/// Unwraps the JSON-RPC error the server answered with.
+pub fn mcp_error(err: ServiceError) -> ErrorData {
+    match err {
+        ServiceError::McpError(error) => error,
+        other => panic!("expected a JSON-RPC error, got {other}"),
+    }
+}
@@ -0,0 +1,551 @@
+//! A local stand-in for the upstream HTTP APIs: OpenWeatherMap, the National
+//! Weather Service alerts feed and Open-Meteo's forecast, archive and air
+//! quality APIs, all served from one base URL.
+//!
+//! Each endpoint serves a scripted [`MockResponse`]. One-shot responses queued
+//! with [`MockUpstream::enqueue`] are served first, then the endpoint
+//! falls back to the response set with [`MockUpstream::respond_with`].
+
+use axum::{
+    extract::{OriginalUri, State},
+    http::{header, HeaderMap, StatusCode},
+    response::{IntoResponse, Response},
+    routing::get,
+    Router,
+};
+use get_weather_poisoned::provider::OpenWeatherMapProvider;
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+use tokio::net::TcpListener;
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Endpoint {
+    // OpenWeatherMap.
+    Geocode,
+    Zip,
+    Reverse,
+    Weather,
+    Forecast,
+    AirPollution,
+    AirPollutionForecast,
+    OneCall,
+    /// The National Weather Service active-alerts feed.
+    Alerts,
+    // Open-Meteo.
+    OpenMeteoForecast,
+    OpenMeteoArchive,
+    OpenMeteoAirQuality,
+}
+
+impl Endpoint {
+    fn path(self) -> &'static str {
+        match self {
+            Endpoint::Geocode => "/geo/1.0/direct",
+            Endpoint::Zip => "/geo/1.0/zip",
+            Endpoint::Reverse => "/geo/1.0/reverse",
+            Endpoint::Weather => "/data/2.5/weather",
+            Endpoint::Forecast => "/data/2.5/forecast",
+            Endpoint::AirPollution => "/data/2.5/air_pollution",
+            Endpoint::AirPollutionForecast => "/data/2.5/air_pollution/forecast",
+            Endpoint::OneCall => "/data/3.0/onecall",
+            Endpoint::Alerts => "/alerts/active",
+            Endpoint::OpenMeteoForecast => "/v1/forecast",
+            Endpoint::OpenMeteoArchive => "/v1/archive",
+            Endpoint::OpenMeteoAirQuality => "/v1/air-quality",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub enum MockResponse {
+    /// 200 with the given JSON body.
+    Json(Value),
+    /// Any status with a JSON body, the way OpenWeatherMap reports errors.
+    Status(u16, Value),
+    /// 200 with a body that is not valid JSON.
+    Malformed,
+    /// Waits before sending the inner response.
+    Slow(Duration, Box<MockResponse>),
+    /// Adds a response header to the inner response.
+    Header(&'static str, String, Box<MockResponse>),
+}
+
+impl MockResponse {
+    pub fn empty_geocoding() -> Self {
+        MockResponse::Json(json!([]))
+    }
+
+    /// How the zip endpoint answers for a code it doesn't know.
+    pub fn zip_not_found() -> Self {
+        MockResponse::Status(404, json!({ "cod": "404", "message": "not found" }))
+    }
+
+    pub fn unauthorized() -> Self {
+        MockResponse::Status(
+            401,
+            json!({
+                "cod": 401,
+                "message": "Invalid API key. Please see https://openweathermap.org/faq#error401 for more info."
+            }),
+        )
+    }
+
+    pub fn rate_limited() -> Self {
+        MockResponse::Status(
+            429,
+            json!({
+                "cod": 429,
+                "message": "Your account is temporary blocked due to exceeding of requests limitation of your subscription type."
+            }),
+        )
+    }
+
+    /// A 429 carrying `Retry-After: secs`.
+    pub fn rate_limited_for(secs: u64) -> Self {
+        MockResponse::Header(
+            "retry-after",
+            secs.to_string(),
+            Box::new(Self::rate_limited()),
+        )
+    }
+
+    pub fn slow(delay: Duration, inner: MockResponse) -> Self {
+        MockResponse::Slow(delay, Box::new(inner))
+    }
+}
+
+/// A request the mock received: the endpoint path, its raw query string and
+/// the client's User-Agent.
+#[derive(Debug, Clone)]
+pub struct RecordedRequest {
+    pub path: String,
+    pub query: String,
+    pub user_agent: Option<String>,
+}
+
+#[derive(Default)]
+struct Script {
+    defaults: HashMap<Endpoint, MockResponse>,
+    queued: HashMap<Endpoint, VecDeque<MockResponse>>,
+    requests: Vec<RecordedRequest>,
+}
+
+#[derive(Clone)]
+struct AppState {
+    script: Arc<Mutex<Script>>,
+    endpoint: Endpoint,
+}
+
+pub struct MockUpstream {
+    base_url: Url,
+    script: Arc<Mutex<Script>>,
+    server: tokio::task::JoinHandle<()>,
+}
+
+impl MockUpstream {
+    /// Starts the mock on an ephemeral port, answering every endpoint with the
+    /// sample London payloads.
+    pub async fn start() -> Self {
+        let script = Arc::new(Mutex::new(Script::default()));
+        {
+            let mut script = script.lock().unwrap();
+            script
+                .defaults
+                .insert(Endpoint::Geocode, MockResponse::Json(sample_geocoding()));
+            script
+                .defaults
+                .insert(Endpoint::Zip, MockResponse::Json(sample_zip()));
+            script
+                .defaults
+                .insert(Endpoint::Reverse, MockResponse::Json(sample_geocoding()));
+            script
+                .defaults
+                .insert(Endpoint::Weather, MockResponse::Json(sample_weather()));
+            script
+                .defaults
+                .insert(Endpoint::Forecast, MockResponse::Json(sample_forecast()));
+            script.defaults.insert(
+                Endpoint::AirPollution,
+                MockResponse::Json(sample_air_pollution(&[(1704110400, 2)])),
+            );
+            script.defaults.insert(
+                Endpoint::AirPollutionForecast,
+                MockResponse::Json(sample_air_pollution(&[
+                    (1704110400, 2),
+                    (1704114000, 3),
+                    (1704117600, 4),
+                    (1704121200, 5),
+                ])),
+            );
+            script.defaults.insert(
+                Endpoint::OneCall,
+                MockResponse::Json(sample_minutely(chrono::Utc::now().timestamp(), 12..32, 1.2)),
+            );
+            script
+                .defaults
+                .insert(Endpoint::Alerts, MockResponse::Json(sample_alerts()));
+            script.defaults.insert(
+                Endpoint::OpenMeteoForecast,
+                MockResponse::Json(sample_history()),
+            );
+            script.defaults.insert(
+                Endpoint::OpenMeteoArchive,
+                MockResponse::Json(sample_history()),
+            );
+            script.defaults.insert(
+                Endpoint::OpenMeteoAirQuality,
+                MockResponse::Json(sample_uv_pollen()),
+            );
+        }
+
+        let mut router = Router::new();
+        for endpoint in [
+            Endpoint::Geocode,
+            Endpoint::Zip,
+            Endpoint::Reverse,
+            Endpoint::Weather,
+            Endpoint::Forecast,
+            Endpoint::AirPollution,
+            Endpoint::AirPollutionForecast,
+            Endpoint::OneCall,
+            Endpoint::Alerts,
+            Endpoint::OpenMeteoForecast,
+            Endpoint::OpenMeteoArchive,
+            Endpoint::OpenMeteoAirQuality,
+        ] {
+            router = router.route(
+                endpoint.path(),
+                get(serve).with_state(AppState {
+                    script: script.clone(),
+                    endpoint,
+                }),
+            );
+        }
+
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base_url = Url::parse(&format!("http://{}", listener.local_addr().unwrap())).unwrap();
+        let server = tokio::spawn(async move {
+            axum::serve(listener, router).await.unwrap();
+        });
+
+        MockUpstream {
+            base_url,
+            script,
+            server,
+        }
+    }
+
+    pub fn base_url(&self) -> Url {
+        self.base_url.clone()
+    }
+
+    /// An OpenWeatherMap provider pointed at the mock.
+    pub fn openweathermap(&self) -> OpenWeatherMapProvider {
+        OpenWeatherMapProvider::new(Some("test-key".to_string())).with_base_url(self.base_url())
+    }
+
+    /// Serves `response` for every subsequent request to `endpoint`.
+    pub fn respond_with(&self, endpoint: Endpoint, response: MockResponse) {
+        self.script
+            .lock()
+            .unwrap()
+            .defaults
+            .insert(endpoint, response);
+    }
+
+    /// Serves each of `responses` once, in order, before the default response.
+    pub fn enqueue(&self, endpoint: Endpoint, responses: impl IntoIterator<Item = MockResponse>) {
+        self.script
+            .lock()
+            .unwrap()
+            .queued
+            .entry(endpoint)
+            .or_default()
+            .extend(responses);
+    }
+
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.script.lock().unwrap().requests.clone()
+    }
+
+    pub fn request_count(&self, endpoint: Endpoint) -> usize {
+        self.requests()
+            .iter()
+            .filter(|r| r.path == endpoint.path())
+            .count()
+    }
+}
+
+impl Drop for MockUpstream {
+    fn drop(&mut self) {
+        self.server.abort();
+    }
+}
+
+async fn serve(
+    State(state): State<AppState>,
+    OriginalUri(uri): OriginalUri,
+    headers: HeaderMap,
+) -> Response {
+    let response = {
+        let mut script = state.script.lock().unwrap();
+        script.requests.push(RecordedRequest {
+            path: uri.path().to_string(),
+            query: uri.query().unwrap_or_default().to_string(),
+            user_agent: headers
+                .get(header::USER_AGENT)
+                .and_then(|v| v.to_str().ok())
+                .map(str::to_string),
+        });
+        script
+            .queued
+            .get_mut(&state.endpoint)
+            .and_then(VecDeque::pop_front)
+            .or_else(|| script.defaults.get(&state.endpoint).cloned())
+            .expect("every endpoint has a default response")
+    };
+    render(response).await
+}
+
+async fn render(mut response: MockResponse) -> Response {
+    let mut headers = Vec::new();
+    loop {
+        let mut rendered = match response {
+            MockResponse::Slow(delay, inner) => {
+                tokio::time::sleep(delay).await;
+                response = *inner;
+                continue;
+            }
+            MockResponse::Header(name, value, inner) => {
+                headers.push((name, value));
+                response = *inner;
+                continue;
+            }
+            MockResponse::Json(body) => axum::Json(body).into_response(),
+            MockResponse::Status(status, body) => {
+                let status = StatusCode::from_u16(status).unwrap();
+                (status, axum::Json(body)).into_response()
+            }
+            MockResponse::Malformed => (
+                [(header::CONTENT_TYPE, "application/json")],
+                "{\"coord\": {\"lon\": -0.12",
+            )
+                .into_response(),
+        };
+        for (name, value) in headers {
+            rendered.headers_mut().insert(name, value.parse().unwrap());
+        }
+        return rendered;
+    }
+}
+
+pub fn sample_geocoding() -> Value {
+    json!([{
+        "name": "London",
+        "local_names": { "en": "London", "fr": "Londres" },
+        "lat": 51.5073219,
+        "lon": -0.1276474,
+        "country": "GB",
+        "state": "England"
+    }])
+}
+
+pub fn sample_zip() -> Value {
+    json!({
+        "zip": "E14",
+        "name": "London",
+        "lat": 51.5004,
+        "lon": -0.0205,
+        "country": "GB"
+    })
+}
+
+/// Three places called Springfield, in the order the API ranks them.
+pub fn ambiguous_geocoding() -> Value {
+    let place = |state: &str, lat: f64, lon: f64| json!({ "name": "Springfield", "lat": lat, "lon": lon, "country": "US", "state": state });
+    json!([
+        place("Illinois", 39.7990175, -89.6439575),
+        place("Missouri", 37.2081729, -93.2922715),
+        place("Massachusetts", 42.1018764, -72.5886727),
+    ])
+}
+
+pub fn sample_weather() -> Value {
+    json!({
+        "coord": { "lon": -0.1276, "lat": 51.5073 },
+        "weather": [{ "id": 500, "main": "Rain", "description": "light rain", "icon": "10d" }],
+        "base": "stations",
+        "main": {
+            "temp": 8.4,
+            "feels_like": 5.9,
+            "temp_min": 7.1,
+            "temp_max": 9.6,
+            "pressure": 1009,
+            "humidity": 82,
+            "sea_level": 1009,
+            "grnd_level": 1005
+        },
+        "visibility": 10000,
+        "wind": { "speed": 4.6, "deg": 230, "gust": 9.8 },
+        "rain": { "1h": 0.4 },
+        "clouds": { "all": 75 },
+        "dt": 1704110400,
+        "sys": { "type": 2, "id": 2075535, "country": "GB", "sunrise": 1704096360, "sunset": 1704124920 },
+        "timezone": 0,
+        "id": 2643743,
+        "name": "London",
+        "cod": 200
+    })
+}
+
+pub fn sample_forecast() -> Value {
+    let entry = |dt: i64, dt_txt: &str, temp: f64, pop: f64| {
+        json!({
+            "dt": dt,
+            "main": { "temp": temp, "feels_like": temp - 2.0, "temp_min": temp, "temp_max": temp, "pressure": 1009, "humidity": 80 },
+            "weather": [{ "id": 500, "main": "Rain", "description": "light rain", "icon": "10d" }],
+            "clouds": { "all": 75 },
+            "wind": { "speed": 5.1, "deg": 225, "gust": 9.0 },
+            "visibility": 10000,
+            "pop": pop,
+            "sys": { "pod": "d" },
+            "dt_txt": dt_txt
+        })
+    };
+    json!({
+        "cod": "200",
+        "message": 0,
+        "cnt": 2,
+        "list": [
+            entry(1704121200, "2024-01-01 15:00:00", 8.1, 0.6),
+            entry(1704132000, "2024-01-01 18:00:00", 7.2, 0.4)
+        ],
+        "city": { "id": 2643743, "name": "London", "country": "GB", "timezone": 0 }
+    })
+}
+
+/// Air pollution readings for London, one per `(dt, aqi)` pair.
+pub fn sample_air_pollution(readings: &[(i64, u32)]) -> Value {
+    let list: Vec<Value> = readings
+        .iter()
+        .map(|&(dt, aqi)| {
+            json!({
+                "dt": dt,
+                "main": { "aqi": aqi },
+                "components": {
+                    "co": 201.94, "no": 0.02, "no2": 12.6, "o3": 68.66,
+                    "so2": 1.6, "pm2_5": 3.2, "pm10": 4.1, "nh3": 0.5
+                }
+            })
+        })
+        .collect();
+    json!({ "coord": { "lon": -0.1276, "lat": 51.5073 }, "list": list })
+}
+
+/// A One Call minutely block of 61 minutes from `now` (Unix seconds), with
+/// `rate` mm/h of precipitation during the `wet` minutes.
+pub fn sample_minutely(now: i64, wet: std::ops::Range<i64>, rate: f64) -> Value {
+    let start = now - now % 60;
+    let minutely: Vec<Value> = (0..61)
+        .map(|minute| {
+            let precipitation = if wet.contains(&minute) { rate } else { 0.0 };
+            json!({ "dt": start + minute * 60, "precipitation": precipitation })
+        })
+        .collect();
+    json!({
+        "lat": 51.5073,
+        "lon": -0.1276,
+        "timezone": "Europe/London",
+        "timezone_offset": 0,
+        "minutely": minutely
+    })
+}
+
+/// Active NWS alerts around Springfield, Illinois, from
+/// `tests/fixtures/nws_alerts.json`.
+pub fn sample_alerts() -> Value {
+    serde_json::from_str(include_str!("../fixtures/nws_alerts.json")).unwrap()
+}
+
+/// Two days of Open-Meteo hourly history. On 2024-01-01 the temperature rises
+/// from 0 to 11.5°C, 2 mm of rain falls, and the hours split evenly between
+/// light rain and overcast with four clear ones; 2024-01-02 has no data yet.
+pub fn sample_history() -> Value {
+    let mut time = Vec::new();
+    let (mut temperature, mut precipitation, mut weather_code) =
+        (Vec::new(), Vec::new(), Vec::new());
+    for hour in 0..24 {
+        time.push(format!("2024-01-01T{:02}:00", hour));
+        temperature.push(json!(f64::from(hour) / 2.0));
+        precipitation.push(json!(if (6..10).contains(&hour) { 0.5 } else { 0.0 }));
+        weather_code.push(json!(match hour {
+            0..10 => 61,
+            10..20 => 3,
+            _ => 0,
+        }));
+    }
+    for hour in 0..24 {
+        time.push(format!("2024-01-02T{:02}:00", hour));
+        temperature.push(Value::Null);
+        precipitation.push(Value::Null);
+        weather_code.push(Value::Null);
+    }
+    json!({
+        "latitude": 51.5,
+        "longitude": -0.12,
+        "timezone": "Europe/London",
+        "hourly_units": { "time": "iso8601", "temperature_2m": "°C", "precipitation": "mm", "weather_code": "wmo code" },
+        "hourly": {
+            "time": time,
+            "temperature_2m": temperature,
+            "precipitation": precipitation,
+            "weather_code": weather_code
+        }
+    })
+}
+
+/// Two days of Open-Meteo hourly UV and pollen. On 2024-05-01 UV peaks at 6.1
+/// at 13:00, grass pollen at 35 at 15:00, tree pollen at 120 at 11:00 and
+/// ragweed at 3 at 18:00, with no mugwort reported. 2024-05-02 has UV only.
+pub fn sample_uv_pollen() -> Value {
+    let mut time = Vec::new();
+    let mut series: HashMap<&str, Vec<Value>> = HashMap::new();
+    for day in ["2024-05-01", "2024-05-02"] {
+        for hour in 0..24u32 {
+            time.push(format!("{}T{:02}:00", day, hour));
+            let first_day = day == "2024-05-01";
+            let at = |peak_hour: u32, peak: f64| {
+                if !first_day {
+                    return Value::Null;
+                }
+                json!((peak - 5.0 * f64::from(peak_hour.abs_diff(hour))).max(0.0))
+            };
+            let uv = if (6..20).contains(&hour) {
+                6.1 - 0.5 * f64::from(hour.abs_diff(13))
+            } else {
+                0.0
+            };
+            for (name, value) in [
+                ("uv_index", json!(uv)),
+                ("grass_pollen", at(15, 35.0)),
+                ("alder_pollen", at(11, 20.0)),
+                ("birch_pollen", at(11, 90.0)),
+                ("olive_pollen", at(11, 10.0)),
+                ("mugwort_pollen", Value::Null),
+                ("ragweed_pollen", at(18, 3.0)),
+            ] {
+                series.entry(name).or_default().push(value);
+            }
+        }
+    }
+    let mut hourly = json!({ "time": time });
+    for (name, values) in series {
+        hourly[name] = json!(values);
+    }
+    json!({ "latitude": 52.52, "longitude": 13.41, "timezone": "Europe/Berlin", "hourly": hourly })
+}
@@ -0,0 +1,6 @@
+//! Shared helpers for integration tests.
+#![allow(dead_code)]
+
+pub mod counting;
+pub mod mcp;
+pub mod mock_upstream;
@@ -0,0 +1,135 @@
+mod support;
+
+use get_weather_poisoned::provider::{OpenWeatherMapProvider, UpstreamRequest, WeatherProvider};
+use proptest::prelude::*;
+use rmcp::model::ErrorCode;
+use serde_json::json;
+use support::{
+    mcp::{mcp_error, McpHarness},
+    mock_upstream::{Endpoint, MockResponse, MockUpstream},
+};
+use url::{form_urlencoded, Url};
+
+fn pairs(query: &str) -> Vec<(String, String)> {
+    form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect()
+}
+
+/// Strings that pass the handler's `city` validation: 1-100 characters,
+/// no control characters, not all whitespace. Biased towards URL syntax.
+fn city() -> impl Strategy<Value = String> {
+    let fragment = prop_oneof![
+        Just("&".to_string()),
+        Just("=".to_string()),
+        Just("?".to_string()),
+        Just("#".to_string()),
+        Just("%".to_string()),
+        Just("%26".to_string()),
+        Just("/".to_string()),
+        Just("+".to_string()),
+        Just(" ".to_string()),
+        Just("appid=".to_string()),
+        Just("limit=50".to_string()),
+        "[^\\p{Cc}]{1,8}",
+    ];
+    prop::collection::vec(fragment, 1..12)
+        .prop_map(|parts| parts.concat().chars().take(100).collect::<String>())
+        .prop_filter("not blank", |s| !s.trim().is_empty())
+}
+
+proptest! {
+    #[test]
+    fn parameter_values_never_change_url_structure(value in any::<String>()) {
+        let base = Url::parse("https://api.example.test/prefix/").unwrap();
+        let url = UpstreamRequest::new(&base, "/geo/1.0/direct")
+            .param("q", &value)
+            .param("limit", 1)
+            .param("appid", "key")
+            .into_url();
+
+        prop_assert_eq!(url.host_str(), Some("api.example.test"));
+        prop_assert_eq!(url.path(), "/prefix/geo/1.0/direct");
+        prop_assert_eq!(url.fragment(), None);
+        prop_assert_eq!(
+            pairs(url.query().unwrap()),
+            vec![
+                ("q".to_string(), value),
+                ("limit".to_string(), "1".to_string()),
+                ("appid".to_string(), "key".to_string()),
+            ]
+        );
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(48))]
+
+    #[test]
+    fn city_reaches_upstream_as_a_single_parameter(city in city()) {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let mock = MockUpstream::start().await;
+            mock.respond_with(Endpoint::Geocode, MockResponse::empty_geocoding());
+            let provider =
+                OpenWeatherMapProvider::new(Some("fuzz-key".to_string())).with_base_url(mock.base_url());
+
+            let _ = provider.geocode(&city).await;
+
+            let requests = mock.requests();
+            assert_eq!(requests.len(), 1);
+            assert_eq!(requests[0].path, "/geo/1.0/direct");
+            assert_eq!(
+                pairs(&requests[0].query),
+                vec![
+                    ("q".to_string(), city.clone()),
+                    ("limit".to_string(), "1".to_string()),
+                    ("appid".to_string(), "fuzz-key".to_string()),
+                ]
+            );
+        });
+    }
+}
+
+#[tokio::test]
+async fn hostile_cities_are_rejected_before_any_request() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let too_long = "a".repeat(101);
+    for city in [
+        "",
+        "   ",
+        "Paris\r\nHost: evil",
+        "Paris\u{0}",
+        too_long.as_str(),
+    ] {
+        let err = harness
+            .call("get-forecast", Some(json!({ "city": city })))
+            .await
+            .unwrap_err();
+        assert_eq!(mcp_error(err).code, ErrorCode::INVALID_PARAMS, "{city:?}");
+    }
+    assert!(mock.requests().is_empty());
+}
+
+#[tokio::test]
+async fn query_injection_attempt_is_sent_as_a_literal_city() {
+    let mock = MockUpstream::start().await;
+    let harness = McpHarness::with_openweathermap(&mock).await;
+
+    let city = "x&limit=50&appid=other";
+    harness
+        .call("get-forecast", Some(json!({ "city": city })))
+        .await
+        .unwrap();
+
+    let geocode = &mock.requests()[0];
+    let pairs = pairs(&geocode.query);
+    assert_eq!(pairs[0], ("q".to_string(), city.to_string()));
+    assert_eq!(pairs.iter().filter(|(k, _)| k == "appid").count(), 1);
+    assert_eq!(pairs.iter().filter(|(k, _)| k == "limit").count(), 1);
+}